@@ -0,0 +1,229 @@
+//! Storage of access tokens, separated from the todo data.
+//!
+//! Tokens are looked up per service (e.g. `"github"`), so a single store can hold the
+//! credentials for several remote services. Data files written by tdo-core 0.2 still
+//! contain the GitHub token; `migrate_token` moves it into a credentials store.
+//!
+//! The library never prompts for tokens itself. Frontends implement `TokenProvider` to
+//! ask their users in whatever way fits them, e.g. on a terminal or in a dialog.
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use serde_json::Value;
+use storage::{self, Format};
+use error::*;
+
+/// Service name of GitHub tokens.
+pub const GITHUB: &str = "github";
+
+/// Service name of GitLab tokens.
+pub const GITLAB: &str = "gitlab";
+
+/// Service name of Gitea tokens.
+pub const GITEA: &str = "gitea";
+
+/// Page where users can generate a new GitHub token for tdo.
+pub const GITHUB_TOKEN_URL: &str =
+    "https://github.com/settings/tokens/new?scopes=repo&description=tdolist";
+
+/// Frontend hook for obtaining tokens that are not stored yet.
+///
+/// Closures of the form `FnMut(&str, &str) -> Option<String>` implement this trait as well.
+pub trait TokenProvider {
+    /// Ask for the token of the given service.
+    ///
+    /// `help_url` points to a page where a new token can be generated, if known.
+    /// Returning `None` means the user declined to enter a token.
+    fn provide_token(&mut self, service: &str, help_url: Option<&str>) -> Option<String>;
+}
+
+impl<F> TokenProvider for F
+    where F: FnMut(&str, Option<&str>) -> Option<String>
+{
+    fn provide_token(&mut self, service: &str, help_url: Option<&str>) -> Option<String> {
+        self(service, help_url)
+    }
+}
+
+/// Look up the token of a service, asking the frontend if the store does not know it.
+///
+/// A token obtained from the provider is saved in the store. Read-only stores are
+/// left untouched.
+///
+/// # Example
+///
+/// ```
+/// # use tdo_core::credentials::*;
+/// let mut store = EnvCredentials::with_prefix("TDO_DOC");
+/// let mut ask = |_: &str, _: Option<&str>| Some("entered token".to_string());
+/// let token = obtain_token(&mut store, &mut ask, GITHUB).unwrap();
+/// assert_eq!(token, Some("entered token".to_string()));
+/// ```
+pub fn obtain_token<C, P>(store: &mut C, provider: &mut P, service: &str) -> TdoResult<Option<String>>
+    where C: Credentials + ?Sized,
+          P: TokenProvider + ?Sized
+{
+    if let Some(token) = store.token(service)? {
+        return Ok(Some(token));
+    }
+    let help_url = if service == GITHUB { Some(GITHUB_TOKEN_URL) } else { None };
+    let token = match provider.provide_token(service, help_url) {
+        Some(ref token) if !token.trim().is_empty() => token.trim().to_string(),
+        _ => return Ok(None),
+    };
+    match store.set_token(service, &token) {
+        Ok(_) => {}
+        Err(Error(ErrorKind::StorageError(storage_error::ErrorKind::ReadOnlyCredentials(_)), _)) => {}
+        Err(e) => return Err(e),
+    }
+    Ok(Some(token))
+}
+
+/// Common interface of all credentials stores.
+pub trait Credentials {
+    /// Look up the token for the given service.
+    fn token(&self, service: &str) -> TdoResult<Option<String>>;
+
+    /// Store the token for the given service.
+    fn set_token(&mut self, service: &str, token: &str) -> TdoResult<()>;
+}
+
+/// Credentials store backed by a separate JSON file.
+///
+/// On Unix systems, the file is only readable by its owner.
+#[derive(Debug, Clone)]
+pub struct FileCredentials {
+    path: String,
+}
+
+impl FileCredentials {
+    /// Create a store for the credentials file at the given path.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tdo_core::credentials::*;
+    /// let store = FileCredentials::new("credentials.json");
+    /// ```
+    pub fn new(path: &str) -> FileCredentials {
+        FileCredentials { path: path.to_string() }
+    }
+
+    fn read(&self) -> TdoResult<BTreeMap<String, String>> {
+        let data = match fs::read_to_string(&self.path) {
+            Ok(data) => data,
+            Err(_) => return Ok(BTreeMap::new()),
+        };
+        match serde_json::from_str(&data) {
+            Ok(tokens) => Ok(tokens),
+            Err(e) => {
+                Err(Error::with_chain(e,
+                                      ErrorKind::StorageError(storage_error::ErrorKind::FileCorrupted(self.path.clone()))))
+            }
+        }
+    }
+}
+
+impl Credentials for FileCredentials {
+    fn token(&self, service: &str) -> TdoResult<Option<String>> {
+        Ok(self.read()?.remove(service))
+    }
+
+    fn set_token(&mut self, service: &str, token: &str) -> TdoResult<()> {
+        let mut tokens = self.read()?;
+        tokens.insert(service.to_string(), token.to_string());
+
+        // `mode` only applies to newly created files, so write a fresh file and move it over the old one
+        let tmp = format!("{}.tmp", self.path);
+        let _ = fs::remove_file(&tmp);
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let res = options.open(&tmp)
+            .and_then(|mut file| {
+                file.write_all(serde_json::to_string_pretty(&tokens)
+                    .expect("a map of strings is always serializable")
+                    .as_bytes())
+            })
+            .and_then(|_| fs::rename(&tmp, &self.path));
+        match res {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                let _ = fs::remove_file(&tmp);
+                Err(storage::save_failure(&self.path, e))
+            }
+        }
+    }
+}
+
+/// Read-only credentials store backed by environment variables.
+///
+/// The token of a service is read from `<PREFIX>_<SERVICE>_TOKEN`, e.g. `TDO_GITHUB_TOKEN`.
+#[derive(Debug, Clone)]
+pub struct EnvCredentials {
+    prefix: String,
+}
+
+impl EnvCredentials {
+    /// Create a store reading variables with the `TDO` prefix.
+    pub fn new() -> EnvCredentials {
+        EnvCredentials::with_prefix("TDO")
+    }
+
+    /// Create a store reading variables with a custom prefix.
+    pub fn with_prefix(prefix: &str) -> EnvCredentials {
+        EnvCredentials { prefix: prefix.to_string() }
+    }
+
+    /// Name of the environment variable holding the token of a service.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tdo_core::credentials::*;
+    /// assert_eq!(EnvCredentials::new().variable("github"), "TDO_GITHUB_TOKEN");
+    /// ```
+    pub fn variable(&self, service: &str) -> String {
+        let service: String = service.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+            .collect();
+        format!("{}_{}_TOKEN", self.prefix, service)
+    }
+}
+
+impl Default for EnvCredentials {
+    fn default() -> EnvCredentials {
+        EnvCredentials::new()
+    }
+}
+
+impl Credentials for EnvCredentials {
+    fn token(&self, service: &str) -> TdoResult<Option<String>> {
+        Ok(env::var(self.variable(service)).ok().filter(|token| !token.is_empty()))
+    }
+
+    fn set_token(&mut self, service: &str, _: &str) -> TdoResult<()> {
+        Err(ErrorKind::StorageError(storage_error::ErrorKind::ReadOnlyCredentials(service.to_string())).into())
+    }
+}
+
+/// Move the GitHub token out of a data file written by an earlier release.
+///
+/// The token is put into the given credentials store and the data file is rewritten
+/// without it. This function returns whether the file contained a token.
+pub fn migrate_token<C: Credentials + ?Sized>(path: &str, store: &mut C) -> TdoResult<bool> {
+    let value = storage::read_value(path, Format::from_path(path))?;
+    match value.get("access_token") {
+        Some(Value::String(token)) => store.set_token(GITHUB, token)?,
+        _ => return Ok(false),
+    }
+    let mut storage = storage::open(path)?;
+    let tdo = storage.load()?;
+    storage.save(&tdo)?;
+    info!("moved the GitHub token out of {}", path);
+    Ok(true)
+}
@@ -0,0 +1,152 @@
+//! Encrypted-at-rest storage of `Tdo` containers.
+//!
+//! The container is serialized to JSON and encrypted with ChaCha20-Poly1305. The key is
+//! derived from a passphrase with Argon2id, using a random salt that is stored in the
+//! file header along with the nonce. Every save uses a fresh salt and nonce.
+use std::fmt;
+use std::fs;
+use argon2::Argon2;
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::aead::rand_core::RngCore;
+use tdo::Tdo;
+use storage::{self, Storage};
+use error::*;
+
+const MAGIC: &[u8] = b"TDOENC01";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Storage backend for passphrase-protected tdo files.
+#[derive(Clone)]
+pub struct EncryptedFile {
+    path: String,
+    passphrase: String,
+}
+
+impl fmt::Debug for EncryptedFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EncryptedFile")
+            .field("path", &self.path)
+            .field("passphrase", &"<hidden>")
+            .finish()
+    }
+}
+
+impl EncryptedFile {
+    /// Create a backend for the encrypted file at the given path.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tdo_core::encryption::*;
+    /// let storage = EncryptedFile::new("foo.tdo", "correct horse battery staple");
+    /// ```
+    pub fn new(path: &str, passphrase: &str) -> EncryptedFile {
+        EncryptedFile {
+            path: path.to_string(),
+            passphrase: passphrase.to_string(),
+        }
+    }
+
+    /// Path of the underlying file.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Re-encrypt the file with a new passphrase.
+    ///
+    /// The file is decrypted with the current passphrase first, so this function returns a
+    /// `StorageError::WrongPassphrase` if that one does not match.
+    pub fn rekey(&mut self, new_passphrase: &str) -> TdoResult<()> {
+        let tdo = self.load()?;
+        let old_passphrase = ::std::mem::replace(&mut self.passphrase, new_passphrase.to_string());
+        let res = self.save(&tdo);
+        if res.is_err() {
+            self.passphrase = old_passphrase;
+        }
+        res
+    }
+}
+
+impl Storage for EncryptedFile {
+    fn load(&self) -> TdoResult<Tdo> {
+        let data = match fs::read(&self.path) {
+            Ok(data) => data,
+            Err(e) => {
+                return Err(Error::with_chain(e,
+                                             ErrorKind::StorageError(storage_error::ErrorKind::FileNotFound(self.path.clone()))))
+            }
+        };
+        if data.len() < MAGIC.len() + SALT_LEN + NONCE_LEN || !data.starts_with(MAGIC) {
+            return Err(ErrorKind::StorageError(storage_error::ErrorKind::NotEncrypted(self.path.clone())).into());
+        }
+        let (salt, rest) = data[MAGIC.len()..].split_at(SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+        let cipher = cipher(&self.path, &self.passphrase, salt)?;
+        let plaintext = match cipher.decrypt(Nonce::from_slice(nonce), ciphertext) {
+            Ok(plaintext) => plaintext,
+            Err(_) => {
+                return Err(ErrorKind::StorageError(storage_error::ErrorKind::WrongPassphrase(self.path.clone()))
+                    .into())
+            }
+        };
+        match serde_json::from_slice(&plaintext) {
+            Ok(value) => storage::from_value(&self.path, value).map(|(tdo, _)| tdo),
+            Err(e) => {
+                Err(Error::with_chain(e,
+                                      ErrorKind::StorageError(storage_error::ErrorKind::FileCorrupted(self.path.clone()))))
+            }
+        }
+    }
+
+    fn save(&mut self, tdo: &Tdo) -> TdoResult<()> {
+        let plaintext = match serde_json::to_vec(tdo) {
+            Ok(plaintext) => plaintext,
+            Err(e) => return Err(storage::save_failure(&self.path, e)),
+        };
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let cipher = cipher(&self.path, &self.passphrase, &salt)?;
+        let ciphertext = match cipher.encrypt(&nonce, &plaintext[..]) {
+            Ok(ciphertext) => ciphertext,
+            Err(_) => {
+                return Err(ErrorKind::StorageError(storage_error::ErrorKind::SaveFailure(self.path.clone()))
+                    .into())
+            }
+        };
+
+        let mut data = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&salt);
+        data.extend_from_slice(&nonce);
+        data.extend_from_slice(&ciphertext);
+        write_atomic(&self.path, &data)
+    }
+}
+
+/// Replace the file at `path` with `data` without ever truncating it in place.
+///
+/// The data is written to a temporary file next to it first, which is then renamed over the
+/// original, so a crash mid-write leaves the previous version intact.
+fn write_atomic(path: &str, data: &[u8]) -> TdoResult<()> {
+    let tmp = format!("{}.tmp", path);
+    match fs::write(&tmp, data).and_then(|_| fs::rename(&tmp, path)) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            let _ = fs::remove_file(&tmp);
+            Err(storage::save_failure(path, e))
+        }
+    }
+}
+
+/// Derive the key of the file at `path` from the passphrase and salt.
+fn cipher(path: &str, passphrase: &str, salt: &[u8]) -> TdoResult<ChaCha20Poly1305> {
+    let mut key = [0u8; 32];
+    match Argon2::default().hash_password_into(passphrase.as_bytes(), salt, &mut key) {
+        Ok(_) => Ok(ChaCha20Poly1305::new(Key::from_slice(&key))),
+        Err(_) => Err(ErrorKind::StorageError(storage_error::ErrorKind::FileCorrupted(path.to_string())).into()),
+    }
+}
@@ -1,4 +1,12 @@
 //! Custom Error Types for errors that may occur when handling todos (or lists).
+//!
+//! Most error kinds carry the context they occured in, e.g. the ID of a todo or the path
+//! of a file. Errors caused by the file system or a parser keep the original error as
+//! their cause, which can be retrieved with `Error::iter`.
+//!
+//! Every error kind has a stable `code` string (e.g. `"storage.file_not_found"`) and a
+//! stable `numeric_code`. Codes are never reused or renumbered, so frontends can rely on
+//! them across releases. `ErrorReport` bundles both with the message for JSON APIs.
 
 /// Custom Result Type for tdo.
 ///
@@ -8,81 +16,376 @@ pub type TdoResult<T> = Result<T>;
 
 
 /// Errors that can arise when working with todo lists.
+///
+/// Numeric codes of this module are in the range 100–199.
 pub mod todo_error {
     error_chain! {
         errors {
             /// The requested item is not in the list.
-            NotInList {
+            NotInList(id: u32) {
                 description("Todo is not in this list")
+                display("There is no todo with ID {}", id)
             }
             /// The requested todo list does not exist.
-            NoSuchList {
+            NoSuchList(name: String) {
                 description("No such list")
+                display("There is no list named '{}'", name)
             }
             /// The default list is tried to be removed.
             CanNotRemoveDefault {
                 description("The default list can no be removed")
             }
             /// A list with the same name already exists.
-            NameAlreadyExists {
+            NameAlreadyExists(name: String) {
                 description("There already exists a list with this name")
+                display("There already exists a list named '{}'", name)
             }
             /// A todo with the same ID already exists.
-            IDAlreadyExists {
+            IDAlreadyExists(id: u32) {
                 description("There already exists a todo with this ID")
+                display("There already exists a todo with ID {}", id)
+            }
+            /// The list is a saved search, which can not hold todos.
+            VirtualList(name: String) {
+                description("Saved searches can not hold todos")
+                display("'{}' is a saved search and can not hold todos", name)
+            }
+        }
+    }
+
+    impl ErrorKind {
+        /// Stable identifier of this kind of error.
+        pub fn code(&self) -> &'static str {
+            match *self {
+                ErrorKind::Msg(_) | ErrorKind::__Nonexhaustive {} => "todo.other",
+                ErrorKind::NotInList(_) => "todo.not_in_list",
+                ErrorKind::NoSuchList(_) => "todo.no_such_list",
+                ErrorKind::CanNotRemoveDefault => "todo.can_not_remove_default",
+                ErrorKind::NameAlreadyExists(_) => "todo.name_already_exists",
+                ErrorKind::IDAlreadyExists(_) => "todo.id_already_exists",
+                ErrorKind::VirtualList(_) => "todo.virtual_list",
+            }
+        }
+
+        /// Stable numeric identifier of this kind of error.
+        pub fn numeric_code(&self) -> u32 {
+            match *self {
+                ErrorKind::Msg(_) | ErrorKind::__Nonexhaustive {} => 100,
+                ErrorKind::NotInList(_) => 101,
+                ErrorKind::NoSuchList(_) => 102,
+                ErrorKind::CanNotRemoveDefault => 103,
+                ErrorKind::NameAlreadyExists(_) => 104,
+                ErrorKind::IDAlreadyExists(_) => 105,
+                ErrorKind::VirtualList(_) => 106,
             }
         }
     }
 }
 
-/// Errors that can arise when interacting with github.
+/// Errors that can arise when interacting with github or another forge.
+///
+/// Numeric codes of this module are in the range 300–399.
 pub mod github_error {
     error_chain! {
         errors {
             /// Repository does not exist
-            DoesNotExist {
+            DoesNotExist(repo: String) {
                 description("Repository does not exist or you have no access to it")
+                display("Repository {} does not exist or you have no access to it", repo)
             }
             /// Bad credentials
             BadCredentials {
                 description("Bad credentials")
             }
             /// Not allowed to move error
-            NotAllowedToMove {
-                description("A github issue is not allowed to be moved out ouf the default list")
+            NotAllowedToMove(id: u32) {
+                description("A todo linked to an issue is not allowed to be moved to this list")
+                display("Todo {} is linked to an issue and cannot be moved to this list under the current move \
+                         policy",
+                        id)
             }
             /// Not a guthub issue eroor
-            NoIssueAsigned {
-                description("There is no github issue asigned to this todo")
+            NoIssueAsigned(id: u32) {
+                description("There is no issue asigned to this todo")
+                display("There is no issue asigned to todo {}", id)
+            }
+            /// No access token available
+            MissingToken {
+                description("No access token available")
             }
             /// Unknown error
-            UnknownError {
+            UnknownError(status: u16) {
                 description("An unknown error occured")
+                display("An unknown error occured (HTTP status {})", status)
+            }
+            /// The todo is linked to an issue already.
+            AlreadyLinked(id: u32) {
+                description("The todo is linked to an issue already")
+                display("Todo {} is linked to an issue already", id)
+            }
+            /// The API rate limit is exceeded. Requests are allowed again at the given
+            /// point in time (seconds since the Unix epoch).
+            RateLimited(reset: u64) {
+                description("API rate limit exceeded")
+                display("API rate limit exceeded, try again after {} (Unix time)", reset)
+            }
+            /// The request could not be sent or its response could not be read.
+            RequestFailed(url: String) {
+                description("Request to the forge failed")
+                display("Request to {} failed", url)
+            }
+            /// The signature of a webhook delivery does not match its payload.
+            InvalidSignature {
+                description("Webhook signature does not match the payload")
+            }
+            /// A webhook payload lacks required fields or is no valid JSON.
+            InvalidPayload(event: String) {
+                description("Webhook payload is invalid")
+                display("Payload of the '{}' webhook event is invalid", event)
+            }
+            /// The list does not track a repository of the forge.
+            NoRepository(list: String) {
+                description("The list does not track a repository")
+                display("List '{}' does not track a repository", list)
+            }
+            /// The response of the forge could not be parsed.
+            InvalidResponse(url: String) {
+                description("The forge sent an invalid response")
+                display("The response to {} could not be parsed", url)
+            }
+        }
+    }
+
+    impl ErrorKind {
+        /// Stable identifier of this kind of error.
+        pub fn code(&self) -> &'static str {
+            match *self {
+                ErrorKind::Msg(_) | ErrorKind::__Nonexhaustive {} => "github.other",
+                ErrorKind::DoesNotExist(_) => "github.does_not_exist",
+                ErrorKind::BadCredentials => "github.bad_credentials",
+                ErrorKind::NotAllowedToMove(_) => "github.not_allowed_to_move",
+                ErrorKind::NoIssueAsigned(_) => "github.no_issue_assigned",
+                ErrorKind::MissingToken => "github.missing_token",
+                ErrorKind::UnknownError(_) => "github.unknown_error",
+                ErrorKind::RequestFailed(_) => "github.request_failed",
+                ErrorKind::RateLimited(_) => "github.rate_limited",
+                ErrorKind::AlreadyLinked(_) => "github.already_linked",
+                ErrorKind::InvalidSignature => "github.invalid_signature",
+                ErrorKind::InvalidPayload(_) => "github.invalid_payload",
+                ErrorKind::NoRepository(_) => "github.no_repository",
+                ErrorKind::InvalidResponse(_) => "github.invalid_response",
+            }
+        }
+
+        /// Stable numeric identifier of this kind of error.
+        pub fn numeric_code(&self) -> u32 {
+            match *self {
+                ErrorKind::Msg(_) | ErrorKind::__Nonexhaustive {} => 300,
+                ErrorKind::DoesNotExist(_) => 301,
+                ErrorKind::BadCredentials => 302,
+                ErrorKind::NotAllowedToMove(_) => 303,
+                ErrorKind::NoIssueAsigned(_) => 304,
+                ErrorKind::MissingToken => 305,
+                ErrorKind::UnknownError(_) => 306,
+                ErrorKind::RequestFailed(_) => 307,
+                ErrorKind::RateLimited(_) => 308,
+                ErrorKind::AlreadyLinked(_) => 309,
+                ErrorKind::InvalidSignature => 310,
+                ErrorKind::InvalidPayload(_) => 311,
+                ErrorKind::NoRepository(_) => 312,
+                ErrorKind::InvalidResponse(_) => 313,
             }
         }
     }
 }
 
 /// The Errors that may occur while interacting with the file system.
+///
+/// Numeric codes of this module are in the range 200–299.
 pub mod storage_error {
     error_chain! {
         errors {
             /// The accessed file is corrupted. This is most likely
             /// because someone edited the JSON file manually.
-            FileCorrupted {
+            FileCorrupted(path: String) {
                 description("File is corrupted")
+                display("File '{}' is corrupted", path)
+            }
+            /// The accessed file is no valid JSON (or TOML/YAML) file.
+            /// Line and column start at 1 and are 0 if the parser did not report them.
+            ParseError(path: String, line: usize, column: usize) {
+                description("File could not be parsed")
+                display("File '{}' could not be parsed (line {}, column {})", path, line, column)
+            }
+            /// The stored container does not have the expected structure.
+            MalformedData(reason: String) {
+                description("Stored data is malformed")
+                display("Stored data is malformed: {}", reason)
             }
             /// The data could not be written to the file.
-            SaveFailure {
+            SaveFailure(path: String) {
                 description("File could not be saved")
+                display("File '{}' could not be saved", path)
             }
             /// The requested file could not be found.
-            FileNotFound {
+            FileNotFound(path: String) {
                 description("File was not found")
+                display("File '{}' was not found", path)
             }
             /// The conversion of an older format failed.
-            UnableToConvert {
+            UnableToConvert(version: String) {
                 description("File could not be converted automatically")
+                display("Files of version {} can not be converted automatically", version)
+            }
+            /// An entry of a file written by an earlier release could not be converted.
+            InvalidEntry(entry: ::migration::SkippedEntry) {
+                description("File contains an entry that could not be converted")
+                display("Entry {} of list '{}' could not be converted: {}",
+                        entry.entry.as_deref().unwrap_or("<list>"),
+                        entry.list,
+                        entry.reason.description())
+            }
+            /// The file was written by a newer release with an unknown format.
+            UnsupportedVersion(version: String) {
+                description("File was written by a newer version of tdo")
+                display("File was written by tdo-core {}, which is newer than this version",
+                        version)
+            }
+            /// The file extension belongs to a format that is not supported or was
+            /// not enabled at compile time.
+            UnsupportedFormat(path: String) {
+                description("File format is not supported")
+                display("The format of '{}' is not supported", path)
+            }
+            /// The passphrase does not match the one the file was encrypted with.
+            WrongPassphrase(path: String) {
+                description("Wrong passphrase for the encrypted file")
+                display("Wrong passphrase for '{}'", path)
+            }
+            /// The file is not an encrypted tdo file.
+            NotEncrypted(path: String) {
+                description("File is not encrypted")
+                display("File '{}' is not encrypted", path)
+            }
+            /// The credentials store can only be read from.
+            ReadOnlyCredentials(service: String) {
+                description("Credentials can not be stored here")
+                display("The {} token can not be stored in a read-only credentials store",
+                        service)
+            }
+        }
+    }
+
+    impl ErrorKind {
+        /// Stable identifier of this kind of error.
+        pub fn code(&self) -> &'static str {
+            match *self {
+                ErrorKind::Msg(_) | ErrorKind::__Nonexhaustive {} => "storage.other",
+                ErrorKind::FileCorrupted(_) => "storage.file_corrupted",
+                ErrorKind::ParseError(..) => "storage.parse_error",
+                ErrorKind::MalformedData(_) => "storage.malformed_data",
+                ErrorKind::SaveFailure(_) => "storage.save_failure",
+                ErrorKind::FileNotFound(_) => "storage.file_not_found",
+                ErrorKind::UnableToConvert(_) => "storage.unable_to_convert",
+                ErrorKind::InvalidEntry(_) => "storage.invalid_entry",
+                ErrorKind::UnsupportedVersion(_) => "storage.unsupported_version",
+                ErrorKind::UnsupportedFormat(_) => "storage.unsupported_format",
+                ErrorKind::WrongPassphrase(_) => "storage.wrong_passphrase",
+                ErrorKind::NotEncrypted(_) => "storage.not_encrypted",
+                ErrorKind::ReadOnlyCredentials(_) => "storage.read_only_credentials",
+            }
+        }
+
+        /// Stable numeric identifier of this kind of error.
+        pub fn numeric_code(&self) -> u32 {
+            match *self {
+                ErrorKind::Msg(_) | ErrorKind::__Nonexhaustive {} => 200,
+                ErrorKind::FileCorrupted(_) => 201,
+                ErrorKind::ParseError(..) => 202,
+                ErrorKind::MalformedData(_) => 203,
+                ErrorKind::SaveFailure(_) => 204,
+                ErrorKind::FileNotFound(_) => 205,
+                ErrorKind::UnableToConvert(_) => 206,
+                ErrorKind::InvalidEntry(_) => 207,
+                ErrorKind::UnsupportedVersion(_) => 208,
+                ErrorKind::UnsupportedFormat(_) => 209,
+                ErrorKind::WrongPassphrase(_) => 210,
+                ErrorKind::NotEncrypted(_) => 211,
+                ErrorKind::ReadOnlyCredentials(_) => 212,
+            }
+        }
+    }
+}
+
+/// Errors that can arise when parsing a query (see the `query` module).
+///
+/// Positions are 0-based character offsets into the query. Numeric codes of this module
+/// are in the range 400–499.
+pub mod query_error {
+    error_chain! {
+        errors {
+            /// The query refers to a field todos do not have.
+            UnknownField(field: String, position: usize) {
+                description("Unknown field")
+                display("Unknown field '{}' at position {}", field, position)
+            }
+            /// The operator can not be used with the field, e.g. `<` with a text field.
+            InvalidOperator(operator: String, position: usize) {
+                description("Operator is not supported by the field")
+                display("Operator '{}' at position {} is not supported by this field", operator, position)
+            }
+            /// The value does not fit the field, e.g. a word for a numeric field.
+            InvalidValue(value: String, position: usize) {
+                description("Invalid value")
+                display("Invalid value '{}' at position {}", value, position)
+            }
+            /// A field, operator or `!` is not followed by anything.
+            MissingValue(position: usize) {
+                description("Missing value")
+                display("Expected a value at position {}", position)
+            }
+            /// A quoted text is not closed.
+            UnterminatedQuote(position: usize) {
+                description("Unterminated quote")
+                display("Quote at position {} is not closed", position)
+            }
+        }
+    }
+
+    impl ErrorKind {
+        /// Stable identifier of this kind of error.
+        pub fn code(&self) -> &'static str {
+            match *self {
+                ErrorKind::Msg(_) | ErrorKind::__Nonexhaustive {} => "query.other",
+                ErrorKind::UnknownField(..) => "query.unknown_field",
+                ErrorKind::InvalidOperator(..) => "query.invalid_operator",
+                ErrorKind::InvalidValue(..) => "query.invalid_value",
+                ErrorKind::MissingValue(_) => "query.missing_value",
+                ErrorKind::UnterminatedQuote(_) => "query.unterminated_quote",
+            }
+        }
+
+        /// Stable numeric identifier of this kind of error.
+        pub fn numeric_code(&self) -> u32 {
+            match *self {
+                ErrorKind::Msg(_) | ErrorKind::__Nonexhaustive {} => 400,
+                ErrorKind::UnknownField(..) => 401,
+                ErrorKind::InvalidOperator(..) => 402,
+                ErrorKind::InvalidValue(..) => 403,
+                ErrorKind::MissingValue(_) => 404,
+                ErrorKind::UnterminatedQuote(_) => 405,
+            }
+        }
+
+        /// Position in the query the error refers to.
+        pub fn position(&self) -> Option<usize> {
+            match *self {
+                ErrorKind::Msg(_) | ErrorKind::__Nonexhaustive {} => None,
+                ErrorKind::UnknownField(_, position) |
+                ErrorKind::InvalidOperator(_, position) |
+                ErrorKind::InvalidValue(_, position) |
+                ErrorKind::MissingValue(position) |
+                ErrorKind::UnterminatedQuote(position) => Some(position),
             }
         }
     }
@@ -93,5 +396,75 @@ error_chain! {
         TodoError(todo_error::Error, todo_error::ErrorKind) #[doc = "An error within the tdo data structures occured."];
         StorageError(storage_error::Error, storage_error::ErrorKind) #[doc = "A storage-related error occured while interacting with the file system."];
         GithubError(github_error::Error, github_error::ErrorKind) #[doc = "A github communication-related error occured."];
+        QueryError(query_error::Error, query_error::ErrorKind) #[doc = "A query could not be parsed."];
+    }
+}
+
+impl ErrorKind {
+    /// Stable identifier of this kind of error, e.g. `"todo.not_in_list"`.
+    ///
+    /// Plain messages that do not belong to any module are reported as `"other"`.
+    pub fn code(&self) -> &'static str {
+        match *self {
+            ErrorKind::Msg(_) | ErrorKind::__Nonexhaustive {} => "other",
+            ErrorKind::TodoError(ref kind) => kind.code(),
+            ErrorKind::StorageError(ref kind) => kind.code(),
+            ErrorKind::GithubError(ref kind) => kind.code(),
+            ErrorKind::QueryError(ref kind) => kind.code(),
+        }
+    }
+
+    /// Stable numeric identifier of this kind of error.
+    ///
+    /// Plain messages that do not belong to any module are reported as `0`.
+    pub fn numeric_code(&self) -> u32 {
+        match *self {
+            ErrorKind::Msg(_) | ErrorKind::__Nonexhaustive {} => 0,
+            ErrorKind::TodoError(ref kind) => kind.numeric_code(),
+            ErrorKind::StorageError(ref kind) => kind.numeric_code(),
+            ErrorKind::GithubError(ref kind) => kind.numeric_code(),
+            ErrorKind::QueryError(ref kind) => kind.numeric_code(),
+        }
+    }
+}
+
+impl Error {
+    /// Create a serializable report of this error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tdo_core::tdo::Tdo;
+    /// let err = Tdo::new().find_id(42).unwrap_err();
+    /// let report = err.report();
+    /// assert_eq!(report.code, "todo.not_in_list");
+    /// assert_eq!(report.numeric_code, 101);
+    /// ```
+    pub fn report(&self) -> ErrorReport {
+        ErrorReport::from(self)
+    }
+}
+
+/// Serializable representation of an error, e.g. for JSON APIs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorReport {
+    /// Stable identifier of the error kind.
+    pub code: String,
+    /// Stable numeric identifier of the error kind.
+    pub numeric_code: u32,
+    /// Human-readable message.
+    pub message: String,
+    /// Messages of the underlying causes, outermost first.
+    pub causes: Vec<String>,
+}
+
+impl<'a> From<&'a Error> for ErrorReport {
+    fn from(err: &'a Error) -> ErrorReport {
+        ErrorReport {
+            code: err.kind().code().to_string(),
+            numeric_code: err.kind().numeric_code(),
+            message: err.to_string(),
+            causes: err.iter().skip(1).map(|cause| cause.to_string()).collect(),
+        }
     }
 }
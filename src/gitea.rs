@@ -0,0 +1,221 @@
+//! The Gitea backend for remote issues.
+//!
+//! Gitea is always self-hosted, so there is no default API location.
+use serde_json::Value;
+use http::{self, HttpClient, Method, RateLimit, Request, Response};
+use remote::{self, Issue, IssueFilter, IssueState, IssueTracker, Pull};
+use todo::{Comment, Forge};
+use error::*;
+
+/// Client for the parts of the Gitea API used by tdo.
+#[derive(Debug)]
+pub struct GiteaClient<H> {
+    http: H,
+    token: String,
+    api_url: String,
+    rate_limit: Option<RateLimit>,
+}
+
+impl<H: HttpClient> GiteaClient<H> {
+    /// Create a client for the API of a Gitea instance, e.g. `https://gitea.example.com/api/v1`.
+    pub fn new(http: H, token: &str, api_url: &str) -> GiteaClient<H> {
+        GiteaClient {
+            http,
+            token: token.to_string(),
+            api_url: api_url.trim_end_matches('/').to_string(),
+            rate_limit: None,
+        }
+    }
+
+    // Gitea expects label IDs when creating issues. Labels the repository does not have
+    // are left out.
+    fn label_ids(&mut self, repo: &str, labels: &[String]) -> TdoResult<Vec<u64>> {
+        let mut url = format!("{}/repos/{}/labels?limit=50", self.api_url, repo);
+        let mut ids = vec![];
+        loop {
+            let response = self.send(Request::new(Method::Get, &url), repo)?;
+            let page: Vec<GiteaLabel> = http::parse(&url, &response)?;
+            ids.extend(page.into_iter().filter(|label| labels.contains(&label.name)).map(|label| label.id));
+            match http::next_page(&response) {
+                Some(next) => url = next,
+                None => break,
+            }
+        }
+        if ids.len() < labels.len() {
+            warn!("some labels do not exist in {} and are left out", repo);
+        }
+        Ok(ids)
+    }
+
+    fn send(&mut self, request: Request, repo: &str) -> TdoResult<Response> {
+        let request = request.header("Authorization", &format!("token {}", self.token))
+            .header("Content-Type", "application/json")
+            .header("User-Agent", concat!("tdo-core/", env!("CARGO_PKG_VERSION")));
+        debug!("{} {}", request.method.as_str(), request.url);
+        let response = self.http.send(&request)?;
+        if let Some(rate_limit) = http::rate_limit(&response) {
+            self.rate_limit = Some(rate_limit);
+        }
+        http::check_status(&response, repo)?;
+        Ok(response)
+    }
+}
+
+impl<H: HttpClient> IssueTracker for GiteaClient<H> {
+    fn forge(&self) -> Forge {
+        Forge::Gitea
+    }
+
+    fn issue(&mut self, repo: &str, number: u32) -> TdoResult<Issue> {
+        let url = format!("{}/repos/{}/issues/{}", self.api_url, repo, number);
+        let response = self.send(Request::new(Method::Get, &url), repo)?;
+        http::parse(&url, &response).map(to_issue)
+    }
+
+    fn open_issues(&mut self, repo: &str, filter: &IssueFilter) -> TdoResult<Vec<Issue>> {
+        let mut params = vec![("state", "open".to_string()),
+                              ("type", "issues".to_string()),
+                              ("limit", "50".to_string())];
+        if !filter.labels.is_empty() {
+            params.push(("labels", filter.labels.join(",")));
+        }
+        if let Some(ref assignee) = filter.assignee {
+            params.push(("assigned_by", assignee.clone()));
+        }
+        if let Some(ref milestone) = filter.milestone {
+            params.push(("milestones", milestone.clone()));
+        }
+        let mut url = format!("{}/repos/{}/issues{}", self.api_url, repo, http::query(&params));
+        let mut issues = vec![];
+        loop {
+            let response = self.send(Request::new(Method::Get, &url), repo)?;
+            let page: Vec<GiteaIssue> = http::parse(&url, &response)?;
+            issues.extend(page.into_iter()
+                .filter(|issue| issue.pull_request.is_none())
+                .map(to_issue));
+            match http::next_page(&response) {
+                Some(next) => url = next,
+                None => return Ok(issues),
+            }
+        }
+    }
+
+    fn create_issue(&mut self, repo: &str, title: &str, labels: &[String]) -> TdoResult<Issue> {
+        let label_ids = if labels.is_empty() { vec![] } else { self.label_ids(repo, labels)? };
+        let url = format!("{}/repos/{}/issues", self.api_url, repo);
+        let body = json!({ "title": title, "labels": label_ids }).to_string();
+        let response = self.send(Request::new(Method::Post, &url).body(body), repo)?;
+        http::parse(&url, &response).map(to_issue)
+    }
+
+    fn set_issue_state(&mut self, repo: &str, number: u32, state: IssueState) -> TdoResult<Issue> {
+        let url = format!("{}/repos/{}/issues/{}", self.api_url, repo, number);
+        let state = match state {
+            IssueState::Open => "open",
+            IssueState::Closed => "closed",
+        };
+        let body = json!({ "state": state }).to_string();
+        let response = self.send(Request::new(Method::Patch, &url).body(body), repo)?;
+        http::parse(&url, &response).map(to_issue)
+    }
+
+    fn set_issue_title(&mut self, repo: &str, number: u32, title: &str) -> TdoResult<Issue> {
+        let url = format!("{}/repos/{}/issues/{}", self.api_url, repo, number);
+        let body = json!({ "title": title }).to_string();
+        let response = self.send(Request::new(Method::Patch, &url).body(body), repo)?;
+        http::parse(&url, &response).map(to_issue)
+    }
+
+    fn add_comment(&mut self, repo: &str, number: u32, body: &str) -> TdoResult<()> {
+        let url = format!("{}/repos/{}/issues/{}/comments", self.api_url, repo, number);
+        let body = json!({ "body": body }).to_string();
+        self.send(Request::new(Method::Post, &url).body(body), repo)?;
+        Ok(())
+    }
+
+    fn comments(&mut self, repo: &str, number: u32) -> TdoResult<Vec<Comment>> {
+        let mut url = format!("{}/repos/{}/issues/{}/comments", self.api_url, repo, number);
+        let mut comments = vec![];
+        loop {
+            let response = self.send(Request::new(Method::Get, &url), repo)?;
+            let page: Vec<GiteaComment> = http::parse(&url, &response)?;
+            for comment in page {
+                comments.push(Comment {
+                    created: http::timestamp(&url, &comment.created_at)?,
+                    body: comment.body,
+                    author: Some(comment.user.login),
+                    remote_id: Some(comment.id),
+                });
+            }
+            match http::next_page(&response) {
+                Some(next) => url = next,
+                None => return Ok(comments),
+            }
+        }
+    }
+
+    fn pull_request(&mut self, repo: &str, number: u32) -> TdoResult<Pull> {
+        let url = format!("{}/repos/{}/pulls/{}", self.api_url, repo, number);
+        let response = self.send(Request::new(Method::Get, &url), repo)?;
+        let pull: GiteaPull = http::parse(&url, &response)?;
+        Ok(Pull {
+            number: pull.number,
+            state: remote::pull_state(pull.state == "closed", pull.merged, pull.draft),
+            title: pull.title,
+            url: pull.html_url,
+        })
+    }
+
+    fn rate_limit(&self) -> Option<RateLimit> {
+        self.rate_limit
+    }
+}
+
+#[derive(Deserialize)]
+struct GiteaComment {
+    id: u64,
+    body: String,
+    user: GiteaUser,
+    created_at: String,
+}
+
+#[derive(Deserialize)]
+struct GiteaUser {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct GiteaIssue {
+    number: u32,
+    title: String,
+    state: String,
+    html_url: String,
+    pull_request: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct GiteaLabel {
+    id: u64,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct GiteaPull {
+    number: u32,
+    title: String,
+    state: String,
+    #[serde(default)]
+    merged: bool,
+    #[serde(default)]
+    draft: bool,
+    html_url: String,
+}
+
+fn to_issue(issue: GiteaIssue) -> Issue {
+    Issue {
+        number: issue.number,
+        state: if issue.state == "closed" { IssueState::Closed } else { IssueState::Open },
+        title: issue.title,
+        url: issue.html_url,
+    }
+}
@@ -0,0 +1,217 @@
+//! The GitHub backend for remote issues.
+use serde_json::Value;
+use http::{self, HttpClient, Method, RateLimit, Request, Response};
+use remote::{self, Fetched, Issue, IssueFilter, IssueState, IssueTracker, Pull};
+use todo::{CacheValidators, Comment, Forge, GHIssueResponse};
+use error::*;
+
+/// Base URL of the public GitHub API.
+pub const API_URL: &str = "https://api.github.com";
+
+/// Client for the parts of the GitHub API used by tdo.
+#[derive(Debug)]
+pub struct GitHubClient<H> {
+    http: H,
+    token: String,
+    api_url: String,
+    rate_limit: Option<RateLimit>,
+}
+
+impl<H: HttpClient> GitHubClient<H> {
+    /// Create a client for the public GitHub API.
+    pub fn new(http: H, token: &str) -> GitHubClient<H> {
+        GitHubClient::with_api_url(http, token, API_URL)
+    }
+
+    /// Create a client for a custom API location, e.g. GitHub Enterprise or a mock server.
+    pub fn with_api_url(http: H, token: &str, api_url: &str) -> GitHubClient<H> {
+        GitHubClient {
+            http,
+            token: token.to_string(),
+            api_url: api_url.trim_end_matches('/').to_string(),
+            rate_limit: None,
+        }
+    }
+
+    fn send(&mut self, request: Request, repo: &str) -> TdoResult<Response> {
+        let request = request.header("Authorization", &format!("token {}", self.token))
+            .header("Accept", "application/vnd.github+json")
+            .header("User-Agent", concat!("tdo-core/", env!("CARGO_PKG_VERSION")));
+        debug!("{} {}", request.method.as_str(), request.url);
+        let response = self.http.send(&request)?;
+        if let Some(rate_limit) = http::rate_limit(&response) {
+            self.rate_limit = Some(rate_limit);
+        }
+        http::check_status(&response, repo)?;
+        Ok(response)
+    }
+}
+
+impl<H: HttpClient> IssueTracker for GitHubClient<H> {
+    fn forge(&self) -> Forge {
+        Forge::GitHub
+    }
+
+    fn issue(&mut self, repo: &str, number: u32) -> TdoResult<Issue> {
+        let url = format!("{}/repos/{}/issues/{}", self.api_url, repo, number);
+        let response = self.send(Request::new(Method::Get, &url), repo)?;
+        http::parse(&url, &response).map(to_issue)
+    }
+
+    fn fetch_issue(&mut self, repo: &str, number: u32, cache: &CacheValidators) -> TdoResult<Fetched> {
+        let url = format!("{}/repos/{}/issues/{}", self.api_url, repo, number);
+        let mut request = Request::new(Method::Get, &url);
+        if let Some(ref etag) = cache.etag {
+            request = request.header("If-None-Match", etag);
+        }
+        if let Some(ref last_modified) = cache.last_modified {
+            request = request.header("If-Modified-Since", last_modified);
+        }
+        let response = self.send(request, repo)?;
+        if response.status == 304 {
+            return Ok(Fetched::NotModified);
+        }
+        let cache = CacheValidators {
+            etag: response.header("ETag").map(|etag| etag.to_string()),
+            last_modified: response.header("Last-Modified").map(|date| date.to_string()),
+        };
+        http::parse(&url, &response).map(|issue| Fetched::Modified(to_issue(issue), cache))
+    }
+
+    fn open_issues(&mut self, repo: &str, filter: &IssueFilter) -> TdoResult<Vec<Issue>> {
+        let mut params = vec![("state", "open".to_string()), ("per_page", "100".to_string())];
+        if !filter.labels.is_empty() {
+            params.push(("labels", filter.labels.join(",")));
+        }
+        if let Some(ref assignee) = filter.assignee {
+            params.push(("assignee", assignee.clone()));
+        }
+        if let Some(ref milestone) = filter.milestone {
+            params.push(("milestone", milestone.clone()));
+        }
+        let mut url = format!("{}/repos/{}/issues{}", self.api_url, repo, http::query(&params));
+        let mut issues = vec![];
+        loop {
+            let response = self.send(Request::new(Method::Get, &url), repo)?;
+            let page: Vec<IssueEntry> = http::parse(&url, &response)?;
+            issues.extend(page.into_iter()
+                .filter(|entry| entry.pull_request.is_none())
+                .map(|entry| to_issue(entry.issue)));
+            match http::next_page(&response) {
+                Some(next) => url = next,
+                None => return Ok(issues),
+            }
+        }
+    }
+
+    fn create_issue(&mut self, repo: &str, title: &str, labels: &[String]) -> TdoResult<Issue> {
+        let url = format!("{}/repos/{}/issues", self.api_url, repo);
+        let body = json!({ "title": title, "labels": labels }).to_string();
+        let response = self.send(Request::new(Method::Post, &url).body(body), repo)?;
+        http::parse(&url, &response).map(to_issue)
+    }
+
+    fn set_issue_state(&mut self, repo: &str, number: u32, state: IssueState) -> TdoResult<Issue> {
+        let url = format!("{}/repos/{}/issues/{}", self.api_url, repo, number);
+        let state = match state {
+            IssueState::Open => "open",
+            IssueState::Closed => "closed",
+        };
+        let body = json!({ "state": state }).to_string();
+        let response = self.send(Request::new(Method::Patch, &url).body(body), repo)?;
+        http::parse(&url, &response).map(to_issue)
+    }
+
+    fn set_issue_title(&mut self, repo: &str, number: u32, title: &str) -> TdoResult<Issue> {
+        let url = format!("{}/repos/{}/issues/{}", self.api_url, repo, number);
+        let body = json!({ "title": title }).to_string();
+        let response = self.send(Request::new(Method::Patch, &url).body(body), repo)?;
+        http::parse(&url, &response).map(to_issue)
+    }
+
+    fn add_comment(&mut self, repo: &str, number: u32, body: &str) -> TdoResult<()> {
+        let url = format!("{}/repos/{}/issues/{}/comments", self.api_url, repo, number);
+        let body = json!({ "body": body }).to_string();
+        self.send(Request::new(Method::Post, &url).body(body), repo)?;
+        Ok(())
+    }
+
+    fn comments(&mut self, repo: &str, number: u32) -> TdoResult<Vec<Comment>> {
+        let mut url = format!("{}/repos/{}/issues/{}/comments?per_page=100", self.api_url, repo, number);
+        let mut comments = vec![];
+        loop {
+            let response = self.send(Request::new(Method::Get, &url), repo)?;
+            let page: Vec<CommentResponse> = http::parse(&url, &response)?;
+            for comment in page {
+                comments.push(Comment {
+                    created: http::timestamp(&url, &comment.created_at)?,
+                    body: comment.body,
+                    author: Some(comment.user.login),
+                    remote_id: Some(comment.id),
+                });
+            }
+            match http::next_page(&response) {
+                Some(next) => url = next,
+                None => return Ok(comments),
+            }
+        }
+    }
+
+    fn pull_request(&mut self, repo: &str, number: u32) -> TdoResult<Pull> {
+        let url = format!("{}/repos/{}/pulls/{}", self.api_url, repo, number);
+        let response = self.send(Request::new(Method::Get, &url), repo)?;
+        let pull: PullResponse = http::parse(&url, &response)?;
+        Ok(Pull {
+            number: pull.number,
+            state: remote::pull_state(pull.state == "closed", pull.merged, pull.draft),
+            title: pull.title,
+            url: pull.html_url,
+        })
+    }
+
+    fn rate_limit(&self) -> Option<RateLimit> {
+        self.rate_limit
+    }
+}
+
+// An entry of the issue list, which also contains pull requests.
+#[derive(Deserialize)]
+struct IssueEntry {
+    #[serde(flatten)]
+    issue: GHIssueResponse,
+    pull_request: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct CommentResponse {
+    id: u64,
+    body: String,
+    user: User,
+    created_at: String,
+}
+
+#[derive(Deserialize)]
+struct User {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct PullResponse {
+    number: u32,
+    title: String,
+    state: String,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    merged: bool,
+    html_url: String,
+}
+
+fn to_issue(issue: GHIssueResponse) -> Issue {
+    Issue {
+        number: issue.number,
+        state: if issue.state == "closed" { IssueState::Closed } else { IssueState::Open },
+        title: issue.title,
+        url: issue.html_url,
+    }
+}
@@ -0,0 +1,205 @@
+//! The GitLab backend for remote issues.
+//!
+//! Repositories are addressed by their full project path (`group/project`), issue
+//! numbers are the project-internal IDs (`iid`) shown in the GitLab interface. Merge
+//! requests are linked as pull requests.
+use http::{self, HttpClient, Method, RateLimit, Request, Response};
+use remote::{self, Issue, IssueFilter, IssueState, IssueTracker, Pull};
+use todo::{Comment, Forge};
+use error::*;
+
+/// Base URL of the API of gitlab.com.
+pub const API_URL: &str = "https://gitlab.com/api/v4";
+
+/// Client for the parts of the GitLab API used by tdo.
+#[derive(Debug)]
+pub struct GitLabClient<H> {
+    http: H,
+    token: String,
+    api_url: String,
+    rate_limit: Option<RateLimit>,
+}
+
+impl<H: HttpClient> GitLabClient<H> {
+    /// Create a client for gitlab.com.
+    pub fn new(http: H, token: &str) -> GitLabClient<H> {
+        GitLabClient::with_api_url(http, token, API_URL)
+    }
+
+    /// Create a client for a self-hosted instance, e.g. `https://gitlab.example.com/api/v4`.
+    pub fn with_api_url(http: H, token: &str, api_url: &str) -> GitLabClient<H> {
+        GitLabClient {
+            http,
+            token: token.to_string(),
+            api_url: api_url.trim_end_matches('/').to_string(),
+            rate_limit: None,
+        }
+    }
+
+    fn project_url(&self, repo: &str) -> String {
+        format!("{}/projects/{}", self.api_url, http::encode(repo))
+    }
+
+    fn send(&mut self, request: Request, repo: &str) -> TdoResult<Response> {
+        let request = request.header("PRIVATE-TOKEN", &self.token)
+            .header("Content-Type", "application/json")
+            .header("User-Agent", concat!("tdo-core/", env!("CARGO_PKG_VERSION")));
+        debug!("{} {}", request.method.as_str(), request.url);
+        let response = self.http.send(&request)?;
+        if let Some(rate_limit) = http::rate_limit(&response) {
+            self.rate_limit = Some(rate_limit);
+        }
+        http::check_status(&response, repo)?;
+        Ok(response)
+    }
+}
+
+impl<H: HttpClient> IssueTracker for GitLabClient<H> {
+    fn forge(&self) -> Forge {
+        Forge::GitLab
+    }
+
+    fn issue(&mut self, repo: &str, number: u32) -> TdoResult<Issue> {
+        let url = format!("{}/issues/{}", self.project_url(repo), number);
+        let response = self.send(Request::new(Method::Get, &url), repo)?;
+        http::parse(&url, &response).map(to_issue)
+    }
+
+    fn open_issues(&mut self, repo: &str, filter: &IssueFilter) -> TdoResult<Vec<Issue>> {
+        let mut params = vec![("state", "opened".to_string()), ("per_page", "100".to_string())];
+        if !filter.labels.is_empty() {
+            params.push(("labels", filter.labels.join(",")));
+        }
+        if let Some(ref assignee) = filter.assignee {
+            params.push(("assignee_username", assignee.clone()));
+        }
+        if let Some(ref milestone) = filter.milestone {
+            params.push(("milestone", milestone.clone()));
+        }
+        let mut url = format!("{}/issues{}", self.project_url(repo), http::query(&params));
+        let mut issues = vec![];
+        loop {
+            let response = self.send(Request::new(Method::Get, &url), repo)?;
+            let page: Vec<GitLabIssue> = http::parse(&url, &response)?;
+            issues.extend(page.into_iter().map(to_issue));
+            match http::next_page(&response) {
+                Some(next) => url = next,
+                None => return Ok(issues),
+            }
+        }
+    }
+
+    fn create_issue(&mut self, repo: &str, title: &str, labels: &[String]) -> TdoResult<Issue> {
+        let url = format!("{}/issues", self.project_url(repo));
+        let body = json!({ "title": title, "labels": labels.join(",") }).to_string();
+        let response = self.send(Request::new(Method::Post, &url).body(body), repo)?;
+        http::parse(&url, &response).map(to_issue)
+    }
+
+    fn set_issue_state(&mut self, repo: &str, number: u32, state: IssueState) -> TdoResult<Issue> {
+        let url = format!("{}/issues/{}", self.project_url(repo), number);
+        let event = match state {
+            IssueState::Open => "reopen",
+            IssueState::Closed => "close",
+        };
+        let body = json!({ "state_event": event }).to_string();
+        let response = self.send(Request::new(Method::Put, &url).body(body), repo)?;
+        http::parse(&url, &response).map(to_issue)
+    }
+
+    fn set_issue_title(&mut self, repo: &str, number: u32, title: &str) -> TdoResult<Issue> {
+        let url = format!("{}/issues/{}", self.project_url(repo), number);
+        let body = json!({ "title": title }).to_string();
+        let response = self.send(Request::new(Method::Put, &url).body(body), repo)?;
+        http::parse(&url, &response).map(to_issue)
+    }
+
+    fn add_comment(&mut self, repo: &str, number: u32, body: &str) -> TdoResult<()> {
+        let url = format!("{}/issues/{}/notes", self.project_url(repo), number);
+        let body = json!({ "body": body }).to_string();
+        self.send(Request::new(Method::Post, &url).body(body), repo)?;
+        Ok(())
+    }
+
+    fn comments(&mut self, repo: &str, number: u32) -> TdoResult<Vec<Comment>> {
+        let mut url = format!("{}/issues/{}/notes?sort=asc&per_page=100", self.project_url(repo), number);
+        let mut comments = vec![];
+        loop {
+            let response = self.send(Request::new(Method::Get, &url), repo)?;
+            let page: Vec<Note> = http::parse(&url, &response)?;
+            for comment in page.into_iter().filter(|note| !note.system) {
+                comments.push(Comment {
+                    created: http::timestamp(&url, &comment.created_at)?,
+                    body: comment.body,
+                    author: Some(comment.author.username),
+                    remote_id: Some(comment.id),
+                });
+            }
+            match http::next_page(&response) {
+                Some(next) => url = next,
+                None => return Ok(comments),
+            }
+        }
+    }
+
+    fn pull_request(&mut self, repo: &str, number: u32) -> TdoResult<Pull> {
+        let url = format!("{}/merge_requests/{}", self.project_url(repo), number);
+        let response = self.send(Request::new(Method::Get, &url), repo)?;
+        let merge_request: MergeRequest = http::parse(&url, &response)?;
+        Ok(Pull {
+            number: merge_request.iid,
+            state: remote::pull_state(merge_request.state != "opened",
+                                      merge_request.state == "merged",
+                                      merge_request.draft),
+            title: merge_request.title,
+            url: merge_request.web_url,
+        })
+    }
+
+    fn rate_limit(&self) -> Option<RateLimit> {
+        self.rate_limit
+    }
+}
+
+// A comment on an issue. System notes record changes like closing the issue.
+#[derive(Deserialize)]
+struct Note {
+    id: u64,
+    body: String,
+    author: Author,
+    created_at: String,
+    #[serde(default)]
+    system: bool,
+}
+
+#[derive(Deserialize)]
+struct Author {
+    username: String,
+}
+
+#[derive(Deserialize)]
+struct GitLabIssue {
+    iid: u32,
+    title: String,
+    state: String,
+    web_url: String,
+}
+
+#[derive(Deserialize)]
+struct MergeRequest {
+    iid: u32,
+    title: String,
+    state: String,
+    #[serde(default)]
+    draft: bool,
+    web_url: String,
+}
+
+fn to_issue(issue: GitLabIssue) -> Issue {
+    Issue {
+        number: issue.iid,
+        state: if issue.state == "closed" { IssueState::Closed } else { IssueState::Open },
+        title: issue.title,
+        url: issue.web_url,
+    }
+}
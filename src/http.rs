@@ -0,0 +1,314 @@
+//! The HTTP layer used to talk to forges.
+//!
+//! All requests go through the `HttpClient` trait, so frontends can plug in their own
+//! HTTP stack and tests can run against a local mock server. With the opt-in `http`
+//! feature, `UreqClient` provides a ready-to-use implementation.
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::de::DeserializeOwned;
+use error::*;
+
+/// HTTP methods used by the forge clients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// `GET`
+    Get,
+    /// `POST`
+    Post,
+    /// `PUT`
+    Put,
+    /// `PATCH`
+    Patch,
+}
+
+impl Method {
+    /// Name of the method as used in the request line.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Patch => "PATCH",
+        }
+    }
+}
+
+/// A request sent through an `HttpClient`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    /// HTTP method.
+    pub method: Method,
+    /// Absolute URL.
+    pub url: String,
+    /// Request headers as name/value pairs.
+    pub headers: Vec<(String, String)>,
+    /// Optional request body.
+    pub body: Option<String>,
+}
+
+impl Request {
+    /// Constructor. Creates a request without headers and body.
+    pub fn new(method: Method, url: &str) -> Request {
+        Request {
+            method,
+            url: url.to_string(),
+            headers: vec![],
+            body: None,
+        }
+    }
+
+    /// Add a header to the request.
+    pub fn header(mut self, name: &str, value: &str) -> Request {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Set the body of the request.
+    pub fn body(mut self, body: String) -> Request {
+        self.body = Some(body);
+        self
+    }
+}
+
+/// A response received through an `HttpClient`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    /// HTTP status code.
+    pub status: u16,
+    /// Response headers as name/value pairs.
+    pub headers: Vec<(String, String)>,
+    /// Response body.
+    pub body: String,
+}
+
+impl Response {
+    /// Look up a header by its case-insensitive name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|header| header.0.eq_ignore_ascii_case(name))
+            .map(|header| header.1.as_str())
+    }
+}
+
+/// Rate limit of a forge API as reported with the last response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Number of requests allowed per period.
+    pub limit: u32,
+    /// Number of requests left in the current period.
+    pub remaining: u32,
+    /// Point in time (seconds since the Unix epoch) when the period ends.
+    pub reset: u64,
+}
+
+/// Common interface of HTTP implementations.
+///
+/// Implementations return every response they receive, including error statuses.
+/// Only failures to send the request or to read the response are errors.
+pub trait HttpClient {
+    /// Send a request and return the response.
+    fn send(&mut self, request: &Request) -> TdoResult<Response>;
+}
+
+impl<H: HttpClient + ?Sized> HttpClient for &mut H {
+    fn send(&mut self, request: &Request) -> TdoResult<Response> {
+        (**self).send(request)
+    }
+}
+
+/// `HttpClient` based on the `ureq` crate.
+#[cfg(feature = "http")]
+#[derive(Debug, Clone)]
+pub struct UreqClient {
+    agent: ::ureq::Agent,
+}
+
+#[cfg(feature = "http")]
+impl UreqClient {
+    /// Constructor. Creates a new client.
+    pub fn new() -> UreqClient {
+        UreqClient { agent: ::ureq::AgentBuilder::new().build() }
+    }
+}
+
+#[cfg(feature = "http")]
+impl Default for UreqClient {
+    fn default() -> UreqClient {
+        UreqClient::new()
+    }
+}
+
+#[cfg(feature = "http")]
+impl HttpClient for UreqClient {
+    fn send(&mut self, request: &Request) -> TdoResult<Response> {
+        let mut call = self.agent.request(request.method.as_str(), &request.url);
+        for (name, value) in &request.headers {
+            call = call.set(name, value);
+        }
+        let result = match request.body {
+            Some(ref body) => call.send_string(body),
+            None => call.call(),
+        };
+        let response = match result {
+            Ok(response) | Err(::ureq::Error::Status(_, response)) => response,
+            Err(e) => return Err(request_failed(&request.url, e)),
+        };
+        let status = response.status();
+        let headers = response.headers_names()
+            .into_iter()
+            .filter_map(|name| {
+                let value = response.header(&name).map(|value| value.to_string());
+                value.map(|value| (name, value))
+            })
+            .collect();
+        match response.into_string() {
+            Ok(body) => {
+                Ok(Response {
+                    status,
+                    headers,
+                    body,
+                })
+            }
+            Err(e) => Err(request_failed(&request.url, e)),
+        }
+    }
+}
+
+/// Map error statuses to `GithubError` kinds.
+pub(crate) fn check_status(response: &Response, repo: &str) -> TdoResult<()> {
+    let kind = match response.status {
+        200..=299 | 304 => return Ok(()),
+        401 => github_error::ErrorKind::BadCredentials,
+        403 if rate_limited(response) => github_error::ErrorKind::RateLimited(rate_limit_reset(response)),
+        429 => github_error::ErrorKind::RateLimited(rate_limit_reset(response)),
+        404 => github_error::ErrorKind::DoesNotExist(repo.to_string()),
+        status => github_error::ErrorKind::UnknownError(status),
+    };
+    Err(ErrorKind::GithubError(kind).into())
+}
+
+pub(crate) fn parse<T: DeserializeOwned>(url: &str, response: &Response) -> TdoResult<T> {
+    serde_json::from_str(&response.body).map_err(|e| {
+        Error::with_chain(e,
+                          ErrorKind::GithubError(github_error::ErrorKind::InvalidResponse(url.to_string())))
+    })
+}
+
+#[cfg(feature = "http")]
+fn request_failed<E>(url: &str, cause: E) -> Error
+    where E: ::std::error::Error + Send + 'static
+{
+    Error::with_chain(cause,
+                      ErrorKind::GithubError(github_error::ErrorKind::RequestFailed(url.to_string())))
+}
+
+// GitHub and Gitea use the `X-` prefix for the rate limit headers, GitLab does not.
+fn rate_limit_header<'a>(response: &'a Response, name: &str) -> Option<&'a str> {
+    response.header(&format!("X-{}", name)).or_else(|| response.header(name))
+}
+
+/// Read the rate limit headers of a response.
+pub(crate) fn rate_limit(response: &Response) -> Option<RateLimit> {
+    let value = |name| rate_limit_header(response, name).and_then(|value| value.trim().parse().ok());
+    Some(RateLimit {
+        limit: value("RateLimit-Limit")?,
+        remaining: value("RateLimit-Remaining")?,
+        reset: rate_limit_header(response, "RateLimit-Reset").and_then(|reset| reset.trim().parse().ok())?,
+    })
+}
+
+fn rate_limited(response: &Response) -> bool {
+    rate_limit_header(response, "RateLimit-Remaining") == Some("0") ||
+    response.header("Retry-After").is_some()
+}
+
+// Point in time (seconds since the epoch) when requests are allowed again.
+fn rate_limit_reset(response: &Response) -> u64 {
+    if let Some(reset) = rate_limit_header(response, "RateLimit-Reset").and_then(|reset| reset.parse().ok()) {
+        return reset;
+    }
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.as_secs()).unwrap_or(0);
+    let delay = response.header("Retry-After").and_then(|delay| delay.parse().ok()).unwrap_or(60);
+    now + delay
+}
+
+/// Extract the URL of the next page from the `Link` header of a response.
+pub(crate) fn next_page(response: &Response) -> Option<String> {
+    response.header("Link")?
+        .split(',')
+        .find(|part| part.contains("rel=\"next\""))
+        .and_then(|part| {
+            let start = part.find('<')? + 1;
+            let end = part.find('>')?;
+            part.get(start..end).map(|url| url.to_string())
+        })
+}
+
+// Read a timestamp of a response, which fails like an unparsable response otherwise.
+pub(crate) fn timestamp(url: &str, value: &str) -> TdoResult<SystemTime> {
+    parse_timestamp(value)
+        .ok_or_else(|| ErrorKind::GithubError(github_error::ErrorKind::InvalidResponse(url.to_string())).into())
+}
+
+/// Parse an RFC 3339 timestamp like `2026-10-18T09:30:00Z`, as used by all forges.
+pub(crate) fn parse_timestamp(value: &str) -> Option<SystemTime> {
+    let number = |range: ::std::ops::Range<usize>| value.get(range).and_then(|digits| digits.parse::<i64>().ok());
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+    if !(1..=12).contains(&month) || value.get(10..11)?.to_uppercase() != "T" {
+        return None;
+    }
+    let mut rest = &value[19..];
+    let mut nanos = 0;
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let digits = fraction.find(|c: char| !c.is_ascii_digit()).unwrap_or(fraction.len());
+        let padded = format!("{:0<9}", &fraction[..digits.min(9)]);
+        nanos = padded.parse::<u32>().ok()?;
+        rest = &fraction[digits..];
+    }
+    let offset = match rest {
+        "Z" | "z" => 0,
+        _ => {
+            let sign = match rest.get(0..1)? {
+                "+" => 1,
+                "-" => -1,
+                _ => return None,
+            };
+            let hours = rest.get(1..3)?.parse::<i64>().ok()?;
+            let minutes = rest.get(4..6)?.parse::<i64>().ok()?;
+            sign * (hours * 3600 + minutes * 60)
+        }
+    };
+    // days since the epoch of the proleptic Gregorian calendar
+    let (y, m) = if month <= 2 { (year - 1, month + 9) } else { (year, month - 3) };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * m + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+    let seconds = days * 86400 + hour * 3600 + minute * 60 + second - offset;
+    if seconds < 0 {
+        return None;
+    }
+    Some(UNIX_EPOCH + Duration::new(seconds as u64, nanos))
+}
+
+/// Build a query string from name/value pairs, starting with `?`.
+pub(crate) fn query(params: &[(&str, String)]) -> String {
+    let params: Vec<String> = params.iter()
+        .map(|&(name, ref value)| format!("{}={}", name, encode(value)))
+        .collect();
+    format!("?{}", params.join("&"))
+}
+
+/// Percent-encode a path segment or query parameter value.
+pub(crate) fn encode(value: &str) -> String {
+    value.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b',' | b'*' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
@@ -1,36 +1,100 @@
-#![allow(missing_docs)]
-use std::fs::File;
-use tdo::Tdo;
-use list::TodoList;
+//! Migration steps for the file formats of earlier tdo releases.
+use serde_json::{Map, Value};
+use migration::{SkippedEntry, SkipReason};
 use error::*;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Tdo01 {
-    pub lists: Vec<TodoList>,
-    version: String,
-}
+/// Convert the pre-0.1 format into the 0.1 format.
+///
+/// Before 0.1, a container was a map of list names to maps of todo IDs to
+/// `[name, done]` pairs. Entries that do not match this shape are skipped.
+pub fn from_pre01(value: Value, skipped: &mut Vec<SkippedEntry>) -> TdoResult<Value> {
+    let old_lists = match value {
+        Value::Object(lists) => lists,
+        _ => {
+            return Err(ErrorKind::StorageError(storage_error::ErrorKind::MalformedData("container is not an object".to_string()))
+                .into())
+        }
+    };
 
-impl Tdo01 {
-    pub fn load(path: &str) -> TdoResult<Tdo01> {
-        match File::open(path) {
-            Ok(file) => {
-                match super::serde_json::from_reader(&file) {
-                    Ok(tdo) => Ok(tdo),
-                    Err(_) => {
-                        Err(ErrorKind::StorageError(storage_error::ErrorKind::FileCorrupted).into())
-                    }
-                }
+    let mut lists = vec![];
+    for (list_name, entries) in old_lists {
+        let entries = match entries {
+            Value::Object(entries) => entries,
+            _ => {
+                skipped.push(SkippedEntry::new(&list_name, None, SkipReason::InvalidList));
+                continue;
+            }
+        };
+        let mut todos = vec![];
+        for (id, entry) in entries {
+            match convert_entry(&id, entry) {
+                Ok(todo) => todos.push(todo),
+                Err(reason) => skipped.push(SkippedEntry::new(&list_name, Some(&id), reason)),
             }
-            Err(_) => Err(ErrorKind::StorageError(storage_error::ErrorKind::FileNotFound).into()),
         }
+        lists.push(json!({
+            "name": list_name,
+            "list": todos,
+        }));
     }
+
+    Ok(json!({
+        "lists": lists,
+        "version": "0.1.0",
+    }))
 }
 
-impl Into<Tdo> for Tdo01 {
-    fn into(self) -> Tdo {
-        println!("Got it here");
-        let mut new_tdo = Tdo::new();
-        new_tdo.lists = self.lists;
-        new_tdo
-    }
+fn convert_entry(id: &str, entry: Value) -> ::std::result::Result<Value, SkipReason> {
+    let id = match id.parse::<u32>() {
+        Ok(id) => id,
+        Err(_) => return Err(SkipReason::InvalidId),
+    };
+    let mut entry = match entry {
+        Value::Array(entry) => entry,
+        _ => return Err(SkipReason::InvalidEntry),
+    };
+    let done = match entry.pop().and_then(|done| done.as_bool()) {
+        Some(x) => x,
+        None => return Err(SkipReason::MissingState),
+    };
+    let name = match entry.pop() {
+        Some(Value::String(x)) => x,
+        _ => return Err(SkipReason::MissingName),
+    };
+    Ok(json!({
+        "id": id,
+        "name": name,
+        "done": done,
+        "github": null,
+    }))
+}
+
+/// Convert the 0.1 format into the 0.2 format, which stores the GitHub access token.
+pub fn from_01(value: Value, _: &mut Vec<SkippedEntry>) -> TdoResult<Value> {
+    let mut tdo: Map<String, Value> = match value {
+        Value::Object(tdo) => tdo,
+        _ => {
+            return Err(ErrorKind::StorageError(storage_error::ErrorKind::MalformedData("container is not an object".to_string()))
+                .into())
+        }
+    };
+    tdo.insert("access_token".to_string(), Value::Null);
+    tdo.insert("version".to_string(), Value::String("0.2.0".to_string()));
+    Ok(Value::Object(tdo))
+}
+
+/// Convert the 0.2 format into the 0.3 format, which no longer contains the GitHub
+/// access token. Loading such a file keeps the token as the session token of the
+/// container; use `credentials::migrate_token` to move it into a credentials store.
+pub fn from_02(value: Value, _: &mut Vec<SkippedEntry>) -> TdoResult<Value> {
+    let mut tdo: Map<String, Value> = match value {
+        Value::Object(tdo) => tdo,
+        _ => {
+            return Err(ErrorKind::StorageError(storage_error::ErrorKind::MalformedData("container is not an object".to_string()))
+                .into())
+        }
+    };
+    tdo.remove("access_token");
+    tdo.insert("version".to_string(), Value::String("0.3.0".to_string()));
+    Ok(Value::Object(tdo))
 }
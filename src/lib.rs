@@ -1,16 +1,53 @@
 //! This is the core for every tdo application
+//!
+//! The library never writes to stdout or reads from stdin. Diagnostics, like the steps
+//! taken to convert files of older releases, are reported through the `log` facade, so
+//! applications decide whether and where they show up.
 #![deny(missing_docs, unsafe_code,
         missing_copy_implementations,
         trivial_casts, trivial_numeric_casts,
         unused_import_braces, unused_qualifications)]
 #![warn(missing_debug_implementations)]
+extern crate serde;
 #[macro_use] extern crate serde_derive;
 #[macro_use] extern crate error_chain;
-extern crate serde_json;
-extern crate json;
+#[macro_use] extern crate log;
+#[macro_use] extern crate serde_json;
+#[cfg(feature = "sqlite")]
+extern crate rusqlite;
+#[cfg(feature = "toml")]
+extern crate toml;
+#[cfg(feature = "yaml")]
+extern crate serde_yaml;
+#[cfg(feature = "encryption")]
+extern crate chacha20poly1305;
+#[cfg(feature = "encryption")]
+extern crate argon2;
+#[cfg(feature = "http")]
+extern crate ureq;
+#[cfg(feature = "webhook")]
+extern crate hmac;
+#[cfg(feature = "webhook")]
+extern crate sha2;
 
 pub mod tdo;
 pub mod list;
 pub mod todo;
 pub mod error;
+pub mod storage;
+pub mod migration;
+pub mod credentials;
+pub mod http;
+pub mod remote;
+pub mod outbox;
+pub mod github;
+pub mod gitlab;
+pub mod gitea;
+pub mod query;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(feature = "encryption")]
+pub mod encryption;
+#[cfg(feature = "webhook")]
+pub mod webhook;
 mod legacy;
\ No newline at end of file
@@ -1,5 +1,6 @@
 //! General implementation of todo lists.
-use todo::Todo;
+use query::Query;
+use todo::{Forge, Todo};
 use error::*;
 
 /// Simple todo list structure.
@@ -13,6 +14,16 @@ pub struct TodoList {
     pub name: String,
     /// The actual vector of `Todo` items.
     pub list: Vec<Todo>,
+    /// Repository (owner/repo) whose issues this list tracks, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<String>,
+    /// Forge hosting `repo`.
+    #[serde(default, skip_serializing_if = "Forge::is_github")]
+    pub forge: Forge,
+    /// Labels of the tracked issues. Only issues carrying all of them are synchronized into
+    /// the list, and issues filed from the list get them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
 }
 
 impl TodoList {
@@ -28,9 +39,38 @@ impl TodoList {
         TodoList {
             name: name.to_string(),
             list: Vec::new(),
+            repo: None,
+            forge: Forge::GitHub,
+            labels: Vec::new(),
         }
     }
 
+    /// Create a new list that tracks the issues of a repository.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tdo_core::list::*;
+    /// let mut list = TodoList::for_repo("bugs", "tdolist/tdo").label("bug");
+    /// ```
+    pub fn for_repo(name: &str, repo: &str) -> TodoList {
+        let mut list = TodoList::new(name);
+        list.repo = Some(repo.to_string());
+        list
+    }
+
+    /// Set the forge hosting the tracked repository, GitHub by default.
+    pub fn with_forge(mut self, forge: Forge) -> TodoList {
+        self.forge = forge;
+        self
+    }
+
+    /// Only track issues carrying this label.
+    pub fn label(mut self, label: &str) -> TodoList {
+        self.labels.push(label.to_string());
+        self
+    }
+
     /// Add a new todo to the list.
     ///
     /// # Example
@@ -52,7 +92,7 @@ impl TodoList {
     pub fn contains_id(&self, id: u32) -> TdoResult<usize> {
         match self.list.iter().position(|x| x.id == id) {
             Some(index) => Ok(index),
-            None => Err(ErrorKind::TodoError(todo_error::ErrorKind::NotInList).into()),
+            None => Err(ErrorKind::TodoError(todo_error::ErrorKind::NotInList(id)).into()),
         }
     }
     /// Mark a todo from the list with the given ID as done.
@@ -61,8 +101,25 @@ impl TodoList {
     /// if the list does not contain any todo with the given ID.
     pub fn done_id(&mut self, id: u32) -> TdoResult<()> {
         match self.contains_id(id) {
-            Ok(index) => Ok(self.list[index].set_done()),
-            _ => Err(ErrorKind::TodoError(todo_error::ErrorKind::NotInList).into()),
+            Ok(index) => {
+                self.list[index].set_done();
+                Ok(())
+            }
+            _ => Err(ErrorKind::TodoError(todo_error::ErrorKind::NotInList(id)).into()),
+        }
+    }
+
+    /// Mark a todo from the list with the given ID as _undone_.
+    ///
+    /// This function returns a `TdoResult`, which will contain a `TodoError::NotInList`
+    /// if the list does not contain any todo with the given ID.
+    pub fn undone_id(&mut self, id: u32) -> TdoResult<()> {
+        match self.contains_id(id) {
+            Ok(index) => {
+                self.list[index].set_undone();
+                Ok(())
+            }
+            _ => Err(ErrorKind::TodoError(todo_error::ErrorKind::NotInList(id)).into()),
         }
     }
 
@@ -73,7 +130,7 @@ impl TodoList {
     pub fn remove_id(&mut self, id: u32) -> TdoResult<Todo> {
         match self.contains_id(id) {
             Ok(index) => Ok(self.list.remove(index)),
-            _ => Err(ErrorKind::TodoError(todo_error::ErrorKind::NotInList).into()),
+            _ => Err(ErrorKind::TodoError(todo_error::ErrorKind::NotInList(id)).into()),
         }
     }
 
@@ -110,7 +167,7 @@ impl TodoList {
     pub fn insert_todo(&mut self, todo: Todo) {
         let insert_id = self.list
             .iter()
-            .fold(0, |acc, &ref x| if todo.id > x.id { acc + 1 } else { acc });
+            .fold(0, |acc, x| if todo.id > x.id { acc + 1 } else { acc });
 
         self.list.insert(insert_id, todo);
     }
@@ -123,6 +180,27 @@ impl Default for TodoList {
         TodoList {
             name: "default".to_string(),
             list: Vec::new(),
+            repo: None,
+            forge: Forge::GitHub,
+            labels: Vec::new(),
         }
     }
 }
+
+/// A todo list or a saved search, as returned by `Tdo::views`.
+#[derive(Debug, Clone)]
+pub struct ListView<'a> {
+    /// Name of the list or search.
+    pub name: &'a str,
+    /// The todos of the list, or the todos of all lists matching the search.
+    pub todos: Vec<&'a Todo>,
+    /// The query of a saved search, `None` for todo lists.
+    pub query: Option<&'a Query>,
+}
+
+impl<'a> ListView<'a> {
+    /// Whether this is a saved search rather than a todo list.
+    pub fn is_virtual(&self) -> bool {
+        self.query.is_some()
+    }
+}
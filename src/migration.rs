@@ -0,0 +1,310 @@
+//! Versioned migrations of stored `Tdo` containers.
+//!
+//! Every stored container carries the `version` of the tdo-core release that wrote it.
+//! Before a container is deserialized, it is run through a pipeline of registered
+//! `Migration` steps until it matches the schema of the running release. Schemas are
+//! identified by the `major.minor` part of the version; patch releases never change the
+//! format. Files written before the `version` field existed are treated as schema `0.0`.
+//!
+//! `dry_run` runs the same pipeline without failing on entries that can not be converted
+//! and returns a `ConversionReport` instead, so applications can show users what a
+//! conversion would do before anything is written.
+use serde_json::Value;
+use tdo::Tdo;
+use legacy;
+use error::*;
+
+/// A single registered migration step between two schema versions.
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    /// Schema version this step migrates from.
+    pub from: &'static str,
+    /// Schema version this step produces.
+    pub to: &'static str,
+    /// Short description of what the step changes.
+    pub description: &'static str,
+    migrate: fn(Value, &mut Vec<SkippedEntry>) -> TdoResult<Value>,
+}
+
+impl Migration {
+    /// Apply this step to a raw container.
+    ///
+    /// This function returns a `StorageError::InvalidEntry` naming the first entry of the
+    /// container that could not be converted.
+    pub fn apply(&self, value: Value) -> TdoResult<Value> {
+        let mut skipped = vec![];
+        let value = (self.migrate)(value, &mut skipped)?;
+        match skipped.into_iter().next() {
+            None => Ok(value),
+            Some(entry) => Err(ErrorKind::StorageError(storage_error::ErrorKind::InvalidEntry(entry)).into()),
+        }
+    }
+}
+
+/// Reasons for skipping an entry during a conversion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// A list is not a map of todos.
+    InvalidList,
+    /// The ID of a todo is not a non-negative number.
+    InvalidId,
+    /// A todo is not a `[name, done]` pair.
+    InvalidEntry,
+    /// The _done_ state of a todo is missing or not a boolean.
+    MissingState,
+    /// The title of a todo is missing or not a string.
+    MissingName,
+}
+
+impl SkipReason {
+    /// Human-readable explanation of the reason.
+    pub fn description(&self) -> &'static str {
+        match *self {
+            SkipReason::InvalidList => "list is not a map of todos",
+            SkipReason::InvalidId => "todo ID is not a number",
+            SkipReason::InvalidEntry => "todo is not a [name, done] pair",
+            SkipReason::MissingState => "todo has no valid done state",
+            SkipReason::MissingName => "todo has no valid title",
+        }
+    }
+}
+
+/// An entry that was skipped during a conversion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedEntry {
+    /// Name of the list the entry belongs to.
+    pub list: String,
+    /// Key of the skipped todo, or `None` if the whole list was skipped.
+    pub entry: Option<String>,
+    /// Why the entry was skipped.
+    pub reason: SkipReason,
+}
+
+impl SkippedEntry {
+    /// Constructor. Creates a new skipped entry.
+    pub fn new(list: &str, entry: Option<&str>, reason: SkipReason) -> SkippedEntry {
+        SkippedEntry {
+            list: list.to_string(),
+            entry: entry.map(|entry| entry.to_string()),
+            reason,
+        }
+    }
+}
+
+/// Summary of what converting a container into the current format would do.
+#[derive(Debug, Clone)]
+pub struct ConversionReport {
+    /// The version the container was stored with.
+    pub source_version: String,
+    /// All steps that would be applied, in order.
+    pub steps: Vec<Migration>,
+    /// Number of lists that would be imported.
+    pub lists: usize,
+    /// Number of todos that would be imported.
+    pub todos: usize,
+    /// Entries that would be dropped, together with the reason.
+    pub skipped: Vec<SkippedEntry>,
+}
+
+static MIGRATIONS: [Migration; 3] = [
+    Migration {
+        from: "0.0",
+        to: "0.1",
+        description: "Convert the map of lists into the versioned list array",
+        migrate: legacy::from_pre01,
+    },
+    Migration {
+        from: "0.1",
+        to: "0.2",
+        description: "Add the GitHub access token",
+        migrate: legacy::from_01,
+    },
+    Migration {
+        from: "0.2",
+        to: "0.3",
+        description: "Remove the GitHub access token from the data file",
+        migrate: legacy::from_02,
+    },
+];
+
+/// All registered migration steps, ordered by their source version.
+pub fn migrations() -> &'static [Migration] {
+    &MIGRATIONS
+}
+
+/// The result of running a container through the migration pipeline.
+#[derive(Debug, Clone)]
+pub struct Migrated {
+    /// The container in the current schema.
+    pub value: Value,
+    /// The version the container was stored with.
+    pub source_version: String,
+    /// All steps that were applied, in order.
+    pub steps: Vec<Migration>,
+}
+
+/// Version written into containers of the current schema.
+///
+/// It only changes together with `migrations()`, independently of the crate release.
+pub const SCHEMA_VERSION: &str = "0.3.0";
+
+/// Schema version of the running tdo-core release.
+pub fn current_schema() -> String {
+    schema_of(SCHEMA_VERSION).expect("schema version is a valid version")
+}
+
+/// Determine the version a raw container was stored with.
+///
+/// Containers without a `version` field are reported as version `0.0.0`.
+pub fn detect_version(value: &Value) -> TdoResult<String> {
+    match value.get("version") {
+        Some(Value::String(version)) => Ok(version.to_owned()),
+        _ if value.is_object() && value.get("lists").is_none() => Ok("0.0.0".to_string()),
+        _ => {
+            Err(ErrorKind::StorageError(storage_error::ErrorKind::MalformedData("container has no version".to_string()))
+                .into())
+        }
+    }
+}
+
+/// Run a raw container through all migration steps required to reach the current schema.
+///
+/// This function returns a `StorageError::UnsupportedVersion` if the container was written
+/// by a newer release and a `StorageError::UnableToConvert` if no migration path exists.
+/// If an entry of the container can not be converted, a `StorageError::InvalidEntry`
+/// names the offending list and entry.
+///
+/// # Example
+///
+/// ```
+/// # extern crate serde_json;
+/// # extern crate tdo_core;
+/// # use tdo_core::migration::*;
+/// # fn main() {
+/// let old = serde_json::json!({ "default": { "1": ["A todo", false] } });
+/// let migrated = migrate(old).unwrap();
+/// assert_eq!(migrated.steps.len(), 3);
+/// # }
+/// ```
+pub fn migrate(value: Value) -> TdoResult<Migrated> {
+    let mut skipped = vec![];
+    let migrated = run(value, &mut skipped)?;
+    match skipped.into_iter().next() {
+        None => Ok(migrated),
+        Some(entry) => Err(ErrorKind::StorageError(storage_error::ErrorKind::InvalidEntry(entry)).into()),
+    }
+}
+
+/// Determine what converting a raw container into the current format would do.
+///
+/// Unlike `migrate`, entries that can not be converted are skipped and listed in the
+/// report. Nothing is written.
+///
+/// # Example
+///
+/// ```
+/// # extern crate serde_json;
+/// # extern crate tdo_core;
+/// # use tdo_core::migration::*;
+/// # fn main() {
+/// let old = serde_json::json!({ "default": { "1": ["A todo", false], "x": ["Broken", true] } });
+/// let report = dry_run(old).unwrap();
+/// assert_eq!(report.source_version, "0.0.0");
+/// assert_eq!(report.todos, 1);
+/// assert_eq!(report.skipped[0].reason, SkipReason::InvalidId);
+/// # }
+/// ```
+pub fn dry_run(value: Value) -> TdoResult<ConversionReport> {
+    let mut skipped = vec![];
+    let migrated = run(value, &mut skipped)?;
+    let tdo: Tdo = match ::serde_json::from_value(migrated.value) {
+        Ok(tdo) => tdo,
+        Err(e) => {
+            let reason = format!("container does not match the current format: {}", e);
+            return Err(Error::with_chain(e,
+                                         ErrorKind::StorageError(storage_error::ErrorKind::MalformedData(reason))))
+        }
+    };
+    Ok(ConversionReport {
+        source_version: migrated.source_version,
+        steps: migrated.steps,
+        lists: tdo.lists.len(),
+        todos: tdo.lists.iter().map(|list| list.list.len()).sum(),
+        skipped,
+    })
+}
+
+/// Check that a container stored with the given version can be read by this release.
+///
+/// This function returns a `StorageError::UnsupportedVersion` if the container was written
+/// by a newer release.
+pub(crate) fn check_version(version: &str) -> TdoResult<()> {
+    if version_key(version)? > version_key(&current_schema())? {
+        warn!("container was written by tdo-core {}, which is newer than {}",
+              version,
+              env!("CARGO_PKG_VERSION"));
+        return Err(ErrorKind::StorageError(storage_error::ErrorKind::UnsupportedVersion(version.to_string()))
+            .into());
+    }
+    Ok(())
+}
+
+fn run(mut value: Value, skipped: &mut Vec<SkippedEntry>) -> TdoResult<Migrated> {
+    let source_version = detect_version(&value)?;
+    check_version(&source_version)?;
+    let target = version_key(&current_schema())?;
+    let mut steps = vec![];
+    loop {
+        let schema = schema_of(&detect_version(&value)?)?;
+        let key = version_key(&schema)?;
+        if key == target {
+            break;
+        }
+        let step = match MIGRATIONS.iter().find(|step| step.from == schema) {
+            Some(step) => *step,
+            None => {
+                return Err(ErrorKind::StorageError(storage_error::ErrorKind::UnableToConvert(source_version))
+                    .into())
+            }
+        };
+        debug!("migrating container from schema {} to {}: {}",
+               step.from,
+               step.to,
+               step.description);
+        let already_skipped = skipped.len();
+        value = (step.migrate)(value, skipped)?;
+        for entry in &skipped[already_skipped..] {
+            warn!("skipping entry {:?} of list {:?}: {}",
+                  entry.entry,
+                  entry.list,
+                  entry.reason.description());
+        }
+        if schema_of(&detect_version(&value)?)? != step.to {
+            return Err(ErrorKind::StorageError(storage_error::ErrorKind::UnableToConvert(source_version))
+                .into());
+        }
+        steps.push(step);
+    }
+    Ok(Migrated {
+        value,
+        source_version,
+        steps,
+    })
+}
+
+/// Reduce a full version to its `major.minor` schema version.
+fn schema_of(version: &str) -> TdoResult<String> {
+    let (major, minor) = version_key(version)?;
+    Ok(format!("{}.{}", major, minor))
+}
+
+fn version_key(version: &str) -> TdoResult<(u32, u32)> {
+    let mut parts = version.split('.').map(|part| part.parse::<u32>());
+    match (parts.next(), parts.next()) {
+        (Some(Ok(major)), Some(Ok(minor))) => Ok((major, minor)),
+        _ => {
+            Err(ErrorKind::StorageError(storage_error::ErrorKind::MalformedData(format!("invalid version '{}'", version)))
+                .into())
+        }
+    }
+}
@@ -0,0 +1,261 @@
+//! Remote operations that are waiting to be sent.
+//!
+//! Closing, reopening, renaming and commenting on a linked todo (see the `remote` module)
+//! changes the todo right away. If the forge can not be reached or the rate limit is
+//! exhausted, the remote part of the change is appended to the outbox of the `Tdo`
+//! container instead of failing. The outbox is saved with the container and replayed by
+//! `flush` once connectivity returns.
+//!
+//! Operations are replayed in the order they were queued. Before an operation is sent,
+//! the issue is fetched again: operations the issue already reflects are dropped, and a
+//! rename is reported as a conflict if the issue was renamed by someone else meanwhile.
+use std::thread;
+use std::time::Duration;
+use remote::{Issue, IssueState, IssueTracker};
+use tdo::Tdo;
+use todo::RemoteIssue;
+use error::*;
+
+/// A change of a remote issue.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Operation {
+    /// Close the issue.
+    Close,
+    /// Reopen the issue.
+    Reopen,
+    /// Rename the issue.
+    Retitle {
+        /// Title of the issue before the rename.
+        from: String,
+        /// New title of the issue.
+        to: String,
+    },
+    /// Add a comment to the issue.
+    Comment {
+        /// Text of the comment.
+        body: String,
+    },
+}
+
+/// An operation in the outbox.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingOperation {
+    /// ID of the todo the operation was queued for.
+    pub todo_id: u32,
+    /// Issue the operation applies to.
+    pub issue: RemoteIssue,
+    /// The operation itself.
+    pub operation: Operation,
+    /// Number of failed attempts to send the operation.
+    #[serde(default)]
+    pub attempts: u32,
+}
+
+/// Outcome of a remote operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    /// The operation was sent to the forge.
+    Sent,
+    /// The forge could not be reached and the operation was queued in the outbox.
+    Queued,
+}
+
+/// How often `flush` tries to send an operation before giving up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Number of attempts per operation, at least one is always made.
+    pub attempts: u32,
+    /// Pause between two attempts.
+    pub delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy::new(3, Duration::from_secs(1))
+    }
+}
+
+impl RetryPolicy {
+    /// Constructor. Creates a new retry policy.
+    pub fn new(attempts: u32, delay: Duration) -> RetryPolicy {
+        RetryPolicy { attempts, delay }
+    }
+}
+
+/// A queued operation that contradicts the current state of its issue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// The operation, which was removed from the outbox.
+    pub pending: PendingOperation,
+    /// The issue as it is now.
+    pub remote: Issue,
+}
+
+/// A queued operation that the forge rejected, e.g. because the issue was deleted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    /// The operation, which was removed from the outbox.
+    pub pending: PendingOperation,
+    /// Why the forge rejected it.
+    pub error: ErrorReport,
+}
+
+/// Summary of a flush.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlushReport {
+    /// Number of operations that were sent.
+    pub sent: usize,
+    /// Number of operations that were dropped because the issue already reflected them.
+    pub already_applied: usize,
+    /// Operations that were dropped because of a conflict.
+    pub conflicts: Vec<Conflict>,
+    /// Operations that were dropped because the forge rejected them.
+    pub failed: Vec<Failure>,
+}
+
+/// Replay all queued operations for issues of the tracker's forge.
+///
+/// Operations that fail because the forge can not be reached are retried according to the
+/// policy. The first operation that still fails aborts the flush with its error; it stays
+/// in the outbox, along with all operations queued after it, while the operations sent up
+/// to that point are removed. An exhausted rate limit aborts the flush right away with a
+/// `GithubError::RateLimited`, so flush again once the limit has reset.
+/// Operations that fail for any other reason, like a deleted issue or bad credentials,
+/// are removed from the outbox and listed in the report, so they never block the
+/// operations queued after them.
+pub fn flush<T: IssueTracker + ?Sized>(tdo: &mut Tdo,
+                                       tracker: &mut T,
+                                       policy: &RetryPolicy)
+                                       -> TdoResult<FlushReport> {
+    let forge = tracker.forge();
+    let mut report = FlushReport::default();
+    let mut index = 0;
+    while index < tdo.outbox.len() {
+        if tdo.outbox[index].issue.forge != forge {
+            index += 1;
+            continue;
+        }
+        let mut tries = 0;
+        let outcome = loop {
+            tries += 1;
+            match replay(tracker, &tdo.outbox[index]) {
+                Err(ref e) if is_unreachable(e) && tries < policy.attempts => {
+                    tdo.outbox[index].attempts += 1;
+                    thread::sleep(policy.delay);
+                }
+                outcome => break outcome,
+            }
+        };
+        match outcome {
+            Ok(Replay::Sent) => report.sent += 1,
+            Ok(Replay::AlreadyApplied) => report.already_applied += 1,
+            Ok(Replay::Conflict(remote)) => {
+                let pending = tdo.outbox[index].clone();
+                warn!("dropping queued operation for todo {}: {}#{} was changed remotely",
+                      pending.todo_id,
+                      pending.issue.repo,
+                      pending.issue.issue_number);
+                report.conflicts.push(Conflict { pending, remote });
+            }
+            Err(e) => {
+                tdo.outbox[index].attempts += 1;
+                if is_transient(&e) {
+                    return Err(e);
+                }
+                let pending = tdo.outbox[index].clone();
+                warn!("dropping queued operation for todo {}: {}", pending.todo_id, e);
+                report.failed.push(Failure {
+                    pending,
+                    error: e.report(),
+                });
+            }
+        }
+        tdo.outbox.remove(index);
+    }
+    info!("flushed {} operations to {}", report.sent, forge.as_str());
+    Ok(report)
+}
+
+// Send an operation right away, or queue it if the forge can not be reached. Operations
+// for issues that have queued operations already are always queued to keep their order.
+pub(crate) fn deliver<T: IssueTracker + ?Sized>(tdo: &mut Tdo,
+                                                tracker: &mut T,
+                                                todo_id: u32,
+                                                issue: RemoteIssue,
+                                                operation: Operation)
+                                                -> TdoResult<Delivery> {
+    let mut pending = PendingOperation {
+        todo_id,
+        issue,
+        operation,
+        attempts: 0,
+    };
+    if !tdo.outbox.iter().any(|queued| queued.issue.same_issue(&pending.issue)) {
+        match send(tracker, &pending) {
+            Ok(()) => return Ok(Delivery::Sent),
+            Err(ref e) if is_transient(e) => pending.attempts = 1,
+            Err(e) => return Err(e),
+        }
+    }
+    info!("queued an operation for {}#{}",
+          pending.issue.repo,
+          pending.issue.issue_number);
+    tdo.outbox.push(pending);
+    Ok(Delivery::Queued)
+}
+
+enum Replay {
+    Sent,
+    AlreadyApplied,
+    Conflict(Issue),
+}
+
+fn replay<T: IssueTracker + ?Sized>(tracker: &mut T, pending: &PendingOperation) -> TdoResult<Replay> {
+    let link = &pending.issue;
+    let issue = match pending.operation {
+        Operation::Comment { .. } => None,
+        _ => Some(tracker.issue(&link.repo, link.issue_number)?),
+    };
+    let applied = match (&pending.operation, issue) {
+        (&Operation::Close, Some(ref issue)) => issue.state == IssueState::Closed,
+        (&Operation::Reopen, Some(ref issue)) => issue.state == IssueState::Open,
+        (Operation::Retitle { from, to }, Some(issue)) => {
+            if issue.title == *to {
+                true
+            } else if issue.title != *from {
+                return Ok(Replay::Conflict(issue));
+            } else {
+                false
+            }
+        }
+        _ => false,
+    };
+    if applied {
+        return Ok(Replay::AlreadyApplied);
+    }
+    send(tracker, pending)?;
+    Ok(Replay::Sent)
+}
+
+fn send<T: IssueTracker + ?Sized>(tracker: &mut T, pending: &PendingOperation) -> TdoResult<()> {
+    let link = &pending.issue;
+    match pending.operation {
+        Operation::Close => tracker.set_issue_state(&link.repo, link.issue_number, IssueState::Closed).map(|_| ()),
+        Operation::Reopen => tracker.set_issue_state(&link.repo, link.issue_number, IssueState::Open).map(|_| ()),
+        Operation::Retitle { ref to, .. } => tracker.set_issue_title(&link.repo, link.issue_number, to).map(|_| ()),
+        Operation::Comment { ref body } => tracker.add_comment(&link.repo, link.issue_number, body),
+    }
+}
+
+// Failures that may go away by trying again later.
+fn is_transient(error: &Error) -> bool {
+    is_unreachable(error) ||
+    matches!(*error.kind(), ErrorKind::GithubError(github_error::ErrorKind::RateLimited(_)))
+}
+
+// Failures to reach the forge, which are worth retrying right away. A rate limit is not,
+// every request is rejected until it resets.
+fn is_unreachable(error: &Error) -> bool {
+    matches!(*error.kind(), ErrorKind::GithubError(github_error::ErrorKind::RequestFailed(_)))
+}
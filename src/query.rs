@@ -0,0 +1,346 @@
+//! Searching todos with a small query language.
+//!
+//! A query is a sequence of terms separated by whitespace. A todo matches the query if it
+//! matches every term. A term preceded by `!` matches the todos the term alone does not
+//! match.
+//!
+//! | Term | Matches todos |
+//! |------|---------------|
+//! | `deploy`, `"deploy to"`, `name:deploy` | whose name contains the text |
+//! | `done`, `done:yes`, `done:no` | that are done, or undone |
+//! | `list:work` | in the list with this name |
+//! | `id:3`, `id>3`, `id<=10` | with a matching ID |
+//! | `repo:tdolist/tdo` or `github.repo:…` | linked to an issue of the repository |
+//! | `issue:12`, `issue>10` or `github.issue…` | linked to an issue with a matching number |
+//! | `forge:gitlab` or `github.forge:…` | linked to an issue on the forge |
+//! | `pr.repo:…`, `pr.number:…`, `pr.state:merged` | linked to a matching pull request |
+//! | `commit.repo:…`, `commit.sha:1a2b` | linked to a matching commit, the SHA by prefix |
+//!
+//! Text is compared ignoring case. Numbers can be compared with `<`, `<=`, `>` and `>=`
+//! besides `:`. Values containing whitespace are quoted, and a name containing the word
+//! `done` is searched for as `"done"`.
+//!
+//! # Example
+//!
+//! ```
+//! # use tdo_core::{list, query, tdo, todo};
+//! let mut tdo = tdo::Tdo::new();
+//! tdo.add_list(list::TodoList::new("work")).unwrap();
+//! tdo.add_todo(Some("work"), todo::Todo::new(0, "Deploy the website", None)).unwrap();
+//! tdo.add_todo(None, todo::Todo::new(1, "Deploy the dotfiles", None)).unwrap();
+//!
+//! let query: query::Query = r#"list:work !done "deploy""#.parse().unwrap();
+//! let found = query.run(&tdo);
+//! assert_eq!(found.len(), 1);
+//! assert_eq!(found[0].1.name, "Deploy the website");
+//! ```
+//!
+//! Queries can be saved in the container under a name (see `SavedSearch`). Saved searches
+//! are shown like todo lists by `Tdo::views`, but todos can not be added or moved to them.
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de;
+use list::TodoList;
+use tdo::Tdo;
+use todo::{Forge, PullState, Todo};
+use error::*;
+
+/// A parsed query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    source: String,
+    terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Term {
+    negated: bool,
+    filter: Filter,
+}
+
+// A single condition. Texts are stored in lowercase.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Filter {
+    Name(String),
+    Done(bool),
+    List(String),
+    Id(Comparison, u32),
+    Repo(String),
+    Issue(Comparison, u32),
+    Forge(Forge),
+    PullRepo(String),
+    PullNumber(Comparison, u32),
+    PullState(PullState),
+    CommitRepo(String),
+    CommitSha(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Equal,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Query {
+    /// Parse a query.
+    ///
+    /// This function returns a `QueryError` with the position of the problem if the query
+    /// is malformed or refers to a field todos do not have.
+    pub fn parse(query: &str) -> TdoResult<Query> {
+        let mut parser = Parser {
+            chars: query.chars().collect(),
+            pos: 0,
+        };
+        let mut terms = vec![];
+        while let Some(term) = parser.term()? {
+            terms.push(term);
+        }
+        Ok(Query {
+            source: query.to_string(),
+            terms,
+        })
+    }
+
+    /// The query as it was written.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Check whether a todo of the given list matches the query.
+    pub fn matches(&self, list: &TodoList, todo: &Todo) -> bool {
+        self.terms.iter().all(|term| term.filter.matches(list, todo) != term.negated)
+    }
+
+    /// Find all matching todos, along with their lists, in the order of the lists.
+    pub fn run<'a>(&self, tdo: &'a Tdo) -> Vec<(&'a TodoList, &'a Todo)> {
+        tdo.lists
+            .iter()
+            .flat_map(|list| {
+                list.list
+                    .iter()
+                    .filter(move |todo| self.matches(list, todo))
+                    .map(move |todo| (list, todo))
+            })
+            .collect()
+    }
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(query: &str) -> TdoResult<Query> {
+        Query::parse(query)
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+// Queries are stored as they were written.
+impl Serialize for Query {
+    fn serialize<S: Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for Query {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> ::std::result::Result<Query, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        Query::parse(&source).map_err(de::Error::custom)
+    }
+}
+
+/// A named query, like "Today" or "Waiting on review".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedSearch {
+    /// Name of the search, unique among lists and searches.
+    pub name: String,
+    /// The query.
+    pub query: Query,
+}
+
+impl SavedSearch {
+    /// Constructor. Parses the query of a new saved search.
+    ///
+    /// This function returns a `QueryError` if the query can not be parsed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tdo_core::query::*;
+    /// let search = SavedSearch::new("Waiting on review", "pr.state:open !done").unwrap();
+    /// ```
+    pub fn new(name: &str, query: &str) -> TdoResult<SavedSearch> {
+        Ok(SavedSearch {
+            name: name.to_string(),
+            query: Query::parse(query)?,
+        })
+    }
+}
+
+impl Filter {
+    fn matches(&self, list: &TodoList, todo: &Todo) -> bool {
+        let github = todo.github.as_ref();
+        let pull = todo.pull_request.as_ref();
+        let commit = todo.commit.as_ref();
+        match *self {
+            Filter::Name(ref text) => todo.name.to_lowercase().contains(text.as_str()),
+            Filter::Done(done) => todo.done == done,
+            Filter::List(ref name) => list.name.to_lowercase() == *name,
+            Filter::Id(comparison, id) => comparison.holds(todo.id, id),
+            Filter::Repo(ref repo) => github.is_some_and(|github| github.repo.to_lowercase() == *repo),
+            Filter::Issue(comparison, number) => {
+                github.is_some_and(|github| comparison.holds(github.issue_number, number))
+            }
+            Filter::Forge(forge) => github.is_some_and(|github| github.forge == forge),
+            Filter::PullRepo(ref repo) => pull.is_some_and(|pull| pull.repo.to_lowercase() == *repo),
+            Filter::PullNumber(comparison, number) => {
+                pull.is_some_and(|pull| comparison.holds(pull.number, number))
+            }
+            Filter::PullState(state) => pull.is_some_and(|pull| pull.state == state),
+            Filter::CommitRepo(ref repo) => commit.is_some_and(|commit| commit.repo.to_lowercase() == *repo),
+            Filter::CommitSha(ref sha) => {
+                commit.is_some_and(|commit| commit.sha.to_lowercase().starts_with(sha.as_str()))
+            }
+        }
+    }
+}
+
+impl Comparison {
+    fn holds(&self, actual: u32, expected: u32) -> bool {
+        match *self {
+            Comparison::Equal => actual == expected,
+            Comparison::Less => actual < expected,
+            Comparison::LessOrEqual => actual <= expected,
+            Comparison::Greater => actual > expected,
+            Comparison::GreaterOrEqual => actual >= expected,
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+
+    fn take_while<F: Fn(char) -> bool>(&mut self, predicate: F) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(&predicate) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    // Parse the next term, `None` at the end of the query.
+    fn term(&mut self) -> TdoResult<Option<Term>> {
+        self.take_while(char::is_whitespace);
+        if self.peek().is_none() {
+            return Ok(None);
+        }
+        let negated = self.peek() == Some('!');
+        if negated {
+            self.pos += 1;
+        }
+        if self.peek() == Some('"') {
+            let text = self.quoted()?;
+            return Ok(Some(Term {
+                negated,
+                filter: Filter::Name(text.to_lowercase()),
+            }));
+        }
+
+        let field_pos = self.pos;
+        let field = self.take_while(|c| !c.is_whitespace() && !matches!(c, ':' | '<' | '>' | '"'));
+        let operator_pos = self.pos;
+        let comparison = match self.peek() {
+            Some(':') => Comparison::Equal,
+            Some('<') if self.chars.get(self.pos + 1) == Some(&'=') => Comparison::LessOrEqual,
+            Some('>') if self.chars.get(self.pos + 1) == Some(&'=') => Comparison::GreaterOrEqual,
+            Some('<') => Comparison::Less,
+            Some('>') => Comparison::Greater,
+            _ => {
+                let word = field + &self.take_while(|c| !c.is_whitespace());
+                let filter = match word.as_str() {
+                    "" => return Err(parse_error(query_error::ErrorKind::MissingValue(self.pos))),
+                    "done" => Filter::Done(true),
+                    _ => Filter::Name(word.to_lowercase()),
+                };
+                return Ok(Some(Term { negated, filter }));
+            }
+        };
+        self.pos += match comparison {
+            Comparison::LessOrEqual | Comparison::GreaterOrEqual => 2,
+            _ => 1,
+        };
+        let operator: String = self.chars[operator_pos..self.pos].iter().collect();
+
+        let value_pos = self.pos;
+        let value = if self.peek() == Some('"') {
+            self.quoted()?
+        } else {
+            self.take_while(|c| !c.is_whitespace())
+        };
+        if value.is_empty() {
+            return Err(parse_error(query_error::ErrorKind::MissingValue(value_pos)));
+        }
+
+        let text = || -> TdoResult<String> {
+            match comparison {
+                Comparison::Equal => Ok(value.to_lowercase()),
+                _ => Err(parse_error(query_error::ErrorKind::InvalidOperator(operator.clone(), operator_pos))),
+            }
+        };
+        let invalid = || parse_error(query_error::ErrorKind::InvalidValue(value.clone(), value_pos));
+        let number = || value.parse::<u32>().map_err(|_| invalid());
+        let filter = match field.as_str() {
+            "name" => Filter::Name(text()?),
+            "done" => {
+                match text()?.as_str() {
+                    "yes" | "true" => Filter::Done(true),
+                    "no" | "false" => Filter::Done(false),
+                    _ => return Err(invalid()),
+                }
+            }
+            "list" => Filter::List(text()?),
+            "id" => Filter::Id(comparison, number()?),
+            "repo" | "github.repo" => Filter::Repo(text()?),
+            "issue" | "github.issue" | "github.issue_number" => Filter::Issue(comparison, number()?),
+            "forge" | "github.forge" => Filter::Forge(Forge::from_name(&text()?).ok_or_else(invalid)?),
+            "pr.repo" => Filter::PullRepo(text()?),
+            "pr.number" => Filter::PullNumber(comparison, number()?),
+            "pr.state" => Filter::PullState(PullState::from_name(&text()?).ok_or_else(invalid)?),
+            "commit.repo" => Filter::CommitRepo(text()?),
+            "commit.sha" => Filter::CommitSha(text()?),
+            _ => return Err(parse_error(query_error::ErrorKind::UnknownField(field, field_pos))),
+        };
+        Ok(Some(Term { negated, filter }))
+    }
+
+    // Read a text in double quotes, starting at the opening quote.
+    fn quoted(&mut self) -> TdoResult<String> {
+        let start = self.pos;
+        self.pos += 1;
+        let text = self.take_while(|c| c != '"');
+        if self.peek().is_none() {
+            return Err(parse_error(query_error::ErrorKind::UnterminatedQuote(start)));
+        }
+        self.pos += 1;
+        Ok(text)
+    }
+}
+
+fn parse_error(kind: query_error::ErrorKind) -> Error {
+    ErrorKind::QueryError(kind).into()
+}
@@ -0,0 +1,531 @@
+//! Forge-agnostic handling of remote issues.
+//!
+//! Every supported forge implements the `IssueTracker` trait (see the `github`, `gitlab`
+//! and `gitea` modules). The functions of this module work with any of them:
+//! `sync` applies the remote state to linked issues and pull requests, `import` pulls the open issues of a
+//! repository into a todo list, `promote` creates an issue for a local todo and `close`,
+//! `reopen`, `retitle` and `comment` change both the todo and its issue.
+//!
+//! A tracker only handles the todos that are linked to its own forge, so containers with
+//! issues on several forges are synchronized by calling `sync` once per tracker.
+//! Failures are reported as `GithubError`s for all forges.
+use outbox::{self, Delivery, Operation};
+use tdo::Tdo;
+use http::RateLimit;
+use todo::{CacheValidators, Comment, Forge, PullState, RemoteIssue, Todo};
+use error::*;
+
+/// State of a remote issue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueState {
+    /// The issue is open.
+    Open,
+    /// The issue is closed.
+    Closed,
+}
+
+/// An issue as reported by a forge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    /// Number of the issue within its repository.
+    pub number: u32,
+    /// Title of the issue.
+    pub title: String,
+    /// Current state of the issue.
+    pub state: IssueState,
+    /// URL of the issue.
+    pub url: String,
+}
+
+/// Result of a conditional request for an issue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fetched {
+    /// The issue did not change since the cache validators were obtained.
+    NotModified,
+    /// The current issue with its new cache validators.
+    Modified(Issue, CacheValidators),
+}
+
+/// A pull request (a merge request on GitLab) as reported by a forge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pull {
+    /// Number of the pull request within its repository.
+    pub number: u32,
+    /// Title of the pull request.
+    pub title: String,
+    /// Current state of the pull request.
+    pub state: PullState,
+    /// URL of the pull request.
+    pub url: String,
+}
+
+// Derive the state of a pull request from the flags most forges report.
+pub(crate) fn pull_state(closed: bool, merged: bool, draft: bool) -> PullState {
+    if merged {
+        PullState::Merged
+    } else if closed {
+        PullState::Closed
+    } else if draft {
+        PullState::Draft
+    } else {
+        PullState::Open
+    }
+}
+
+/// Restricts which issues are imported.
+///
+/// # Example
+///
+/// ```
+/// # use tdo_core::remote::IssueFilter;
+/// let filter = IssueFilter::new().label("bug").assignee("octocat");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IssueFilter {
+    /// Only issues carrying all of these labels.
+    pub labels: Vec<String>,
+    /// Only issues assigned to this user.
+    pub assignee: Option<String>,
+    /// Only issues of this milestone, in the notation of the forge.
+    pub milestone: Option<String>,
+}
+
+impl IssueFilter {
+    /// Constructor. Creates a filter that matches all open issues.
+    pub fn new() -> IssueFilter {
+        IssueFilter::default()
+    }
+
+    /// Require a label.
+    pub fn label(mut self, label: &str) -> IssueFilter {
+        self.labels.push(label.to_string());
+        self
+    }
+
+    /// Require an assignee.
+    pub fn assignee(mut self, assignee: &str) -> IssueFilter {
+        self.assignee = Some(assignee.to_string());
+        self
+    }
+
+    /// Require a milestone.
+    pub fn milestone(mut self, milestone: &str) -> IssueFilter {
+        self.milestone = Some(milestone.to_string());
+        self
+    }
+}
+
+/// Common interface of all forge backends.
+///
+/// Repositories are identified by their full path, e.g. `owner/repo`.
+pub trait IssueTracker {
+    /// The forge this tracker talks to.
+    fn forge(&self) -> Forge;
+
+    /// Fetch a single issue.
+    ///
+    /// This function returns a `GithubError::DoesNotExist` if the repository or issue
+    /// can not be found and a `GithubError::BadCredentials` if the token was rejected.
+    fn issue(&mut self, repo: &str, number: u32) -> TdoResult<Issue>;
+
+    /// Fetch a single issue unless it did not change since the cache validators were
+    /// obtained.
+    ///
+    /// The default implementation always fetches the issue and returns no validators.
+    fn fetch_issue(&mut self, repo: &str, number: u32, _cache: &CacheValidators) -> TdoResult<Fetched> {
+        self.issue(repo, number).map(|issue| Fetched::Modified(issue, CacheValidators::default()))
+    }
+
+    /// Fetch all open issues of a repository that match the filter, following all pages.
+    /// Pull and merge requests are left out.
+    fn open_issues(&mut self, repo: &str, filter: &IssueFilter) -> TdoResult<Vec<Issue>>;
+
+    /// Create a new issue with the given labels.
+    fn create_issue(&mut self, repo: &str, title: &str, labels: &[String]) -> TdoResult<Issue>;
+
+    /// Open or close an issue.
+    fn set_issue_state(&mut self, repo: &str, number: u32, state: IssueState) -> TdoResult<Issue>;
+
+    /// Change the title of an issue.
+    fn set_issue_title(&mut self, repo: &str, number: u32, title: &str) -> TdoResult<Issue>;
+
+    /// Add a comment to an issue.
+    fn add_comment(&mut self, repo: &str, number: u32, body: &str) -> TdoResult<()>;
+
+    /// Fetch all comments on an issue, oldest first.
+    fn comments(&mut self, repo: &str, number: u32) -> TdoResult<Vec<Comment>>;
+
+    /// Fetch a single pull request.
+    fn pull_request(&mut self, repo: &str, number: u32) -> TdoResult<Pull>;
+
+    /// Rate limit reported with the last response, if the forge sends one.
+    fn rate_limit(&self) -> Option<RateLimit> {
+        None
+    }
+}
+
+impl<T: IssueTracker + ?Sized> IssueTracker for &mut T {
+    fn forge(&self) -> Forge {
+        (**self).forge()
+    }
+
+    fn issue(&mut self, repo: &str, number: u32) -> TdoResult<Issue> {
+        (**self).issue(repo, number)
+    }
+
+    fn fetch_issue(&mut self, repo: &str, number: u32, cache: &CacheValidators) -> TdoResult<Fetched> {
+        (**self).fetch_issue(repo, number, cache)
+    }
+
+    fn open_issues(&mut self, repo: &str, filter: &IssueFilter) -> TdoResult<Vec<Issue>> {
+        (**self).open_issues(repo, filter)
+    }
+
+    fn create_issue(&mut self, repo: &str, title: &str, labels: &[String]) -> TdoResult<Issue> {
+        (**self).create_issue(repo, title, labels)
+    }
+
+    fn set_issue_state(&mut self, repo: &str, number: u32, state: IssueState) -> TdoResult<Issue> {
+        (**self).set_issue_state(repo, number, state)
+    }
+
+    fn set_issue_title(&mut self, repo: &str, number: u32, title: &str) -> TdoResult<Issue> {
+        (**self).set_issue_title(repo, number, title)
+    }
+
+    fn add_comment(&mut self, repo: &str, number: u32, body: &str) -> TdoResult<()> {
+        (**self).add_comment(repo, number, body)
+    }
+
+    fn comments(&mut self, repo: &str, number: u32) -> TdoResult<Vec<Comment>> {
+        (**self).comments(repo, number)
+    }
+
+    fn pull_request(&mut self, repo: &str, number: u32) -> TdoResult<Pull> {
+        (**self).pull_request(repo, number)
+    }
+
+    fn rate_limit(&self) -> Option<RateLimit> {
+        (**self).rate_limit()
+    }
+}
+
+/// Summary of a synchronization run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Number of linked issues and pull requests that were checked.
+    pub checked: usize,
+    /// IDs of todos that were marked as done because their issue was closed.
+    pub closed: Vec<u32>,
+    /// IDs of todos whose title was updated.
+    pub renamed: Vec<u32>,
+    /// IDs of todos that were marked as done because their pull request was merged.
+    pub merged: Vec<u32>,
+    /// Number of issues that did not change since the last synchronization.
+    pub not_modified: usize,
+    /// IDs of the todos created for new issues of repositories tracked by lists.
+    pub added: Vec<u32>,
+}
+
+/// Apply the state of all linked issues and pull requests of the tracker's forge to
+/// their todos.
+///
+/// Todos whose issue was closed or whose pull request was merged are marked as done and
+/// titles are updated to the current issue titles. The stored state of pull requests is
+/// refreshed, but a pull request that was closed without being merged leaves its todo
+/// untouched. Todos that are done locally are never reopened. Linked commits are not
+/// checked.
+///
+/// Issues are fetched with conditional requests, using the cache validators stored with
+/// the links, so issues that did not change since the last synchronization leave their
+/// todos untouched and, on GitHub, do not count against the rate limit.
+///
+/// Afterwards, the open issues of every list that tracks a repository of the tracker's
+/// forge are imported into that list, limited to the issues carrying the labels of the
+/// list.
+/// The first failing request aborts the synchronization; changes applied up to that
+/// point are kept.
+pub fn sync<T: IssueTracker + ?Sized>(tdo: &mut Tdo, tracker: &mut T) -> TdoResult<SyncReport> {
+    let forge = tracker.forge();
+    let mut report = SyncReport::default();
+    for list in &mut tdo.lists {
+        for todo in &mut list.list {
+            sync_pull_request(tracker, todo, &mut report)?;
+            let fetched = match todo.github {
+                Some(ref link) if link.forge == forge => {
+                    tracker.fetch_issue(&link.repo, link.issue_number, &link.cache)?
+                }
+                _ => continue,
+            };
+            report.checked += 1;
+            let issue = match fetched {
+                Fetched::NotModified => {
+                    report.not_modified += 1;
+                    continue;
+                }
+                Fetched::Modified(issue, cache) => {
+                    if let Some(ref mut link) = todo.github {
+                        link.cache = cache;
+                    }
+                    issue
+                }
+            };
+            if issue.state == IssueState::Closed && !todo.done {
+                todo.set_done();
+                report.closed.push(todo.id);
+            }
+            if issue.title != todo.name {
+                todo.edit(&issue.title);
+                report.renamed.push(todo.id);
+            }
+        }
+    }
+    let tracked: Vec<(String, String, Vec<String>)> = tdo.lists
+        .iter()
+        .filter(|list| list.forge == forge)
+        .filter_map(|list| list.repo.as_ref().map(|repo| (list.name.clone(), repo.clone(), list.labels.clone())))
+        .collect();
+    for (list_name, repo, labels) in tracked {
+        let filter = IssueFilter {
+            labels,
+            ..IssueFilter::default()
+        };
+        let imported = import(tdo, tracker, &repo, &list_name, &filter)?;
+        report.added.extend(imported.added);
+        for id in imported.renamed {
+            if !report.renamed.contains(&id) {
+                report.renamed.push(id);
+            }
+        }
+    }
+    info!("synchronized {} todos linked to {}", report.checked, forge.as_str());
+    Ok(report)
+}
+
+fn sync_pull_request<T: IssueTracker + ?Sized>(tracker: &mut T,
+                                               todo: &mut Todo,
+                                               report: &mut SyncReport)
+                                               -> TdoResult<()> {
+    let forge = tracker.forge();
+    let link = match todo.pull_request {
+        Some(ref mut link) if link.forge == forge => link,
+        _ => return Ok(()),
+    };
+    let pull = tracker.pull_request(&link.repo, link.number)?;
+    link.state = pull.state;
+    report.checked += 1;
+    if pull.state == PullState::Merged && !todo.done {
+        todo.set_done();
+        report.merged.push(todo.id);
+    }
+    Ok(())
+}
+
+/// Summary of an import run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// IDs of the todos created for new issues.
+    pub added: Vec<u32>,
+    /// IDs of already imported todos whose title was updated.
+    pub renamed: Vec<u32>,
+    /// Number of issues that were already imported and unchanged.
+    pub unchanged: usize,
+}
+
+/// Import the open issues of a repository into a todo list.
+///
+/// Issues are identified by forge, repository and issue number, so running the import
+/// again does not create duplicates. Todos of issues that were imported before, into any
+/// list, only get their titles updated.
+/// This function returns a `TodoError::NoSuchList` if the target list does not exist and a
+/// `TodoError::VirtualList` if it names a saved search.
+pub fn import<T: IssueTracker + ?Sized>(tdo: &mut Tdo,
+                                        tracker: &mut T,
+                                        repo: &str,
+                                        list_name: &str,
+                                        filter: &IssueFilter)
+                                        -> TdoResult<ImportReport> {
+    let index = tdo.get_list_index(list_name)?;
+    let forge = tracker.forge();
+    let issues = tracker.open_issues(repo, filter)?;
+    let mut report = ImportReport::default();
+    for issue in issues {
+        let link = RemoteIssue::with_forge(forge, repo, issue.number);
+        let existing = tdo.lists
+            .iter_mut()
+            .flat_map(|list| list.list.iter_mut())
+            .find(|todo| todo.github.as_ref().is_some_and(|github| github.same_issue(&link)));
+        match existing {
+            Some(todo) if todo.name != issue.title => {
+                todo.edit(&issue.title);
+                report.renamed.push(todo.id);
+            }
+            Some(_) => report.unchanged += 1,
+            None => {
+                let id = tdo.get_highest_id() + 1;
+                tdo.lists[index].add(Todo::new(id, &issue.title, Some(link)));
+                report.added.push(id);
+            }
+        }
+    }
+    info!("imported {} new issues of {} into '{}'",
+          report.added.len(),
+          repo,
+          list_name);
+    Ok(report)
+}
+
+/// Create an issue for a local todo and link the todo to it.
+///
+/// The issue gets the title of the todo. This function returns a
+/// `GithubError::AlreadyLinked` if the todo is linked to an issue already.
+pub fn promote<T: IssueTracker + ?Sized>(tdo: &mut Tdo,
+                                         tracker: &mut T,
+                                         id: u32,
+                                         repo: &str)
+                                         -> TdoResult<RemoteIssue> {
+    file(tdo, tracker, id, repo, &[])
+}
+
+/// Create an issue for a local todo in the repository its list tracks.
+///
+/// The issue gets the title of the todo and the labels of the list. This function
+/// returns a `GithubError::NoRepository` if the list of the todo does not track a
+/// repository of the tracker's forge and a `GithubError::AlreadyLinked` if the todo is
+/// linked to an issue already.
+pub fn publish<T: IssueTracker + ?Sized>(tdo: &mut Tdo, tracker: &mut T, id: u32) -> TdoResult<RemoteIssue> {
+    let list = &tdo.lists[tdo.find_id(id)?];
+    let repo = match list.repo {
+        Some(ref repo) if list.forge == tracker.forge() => repo.clone(),
+        _ => return Err(ErrorKind::GithubError(github_error::ErrorKind::NoRepository(list.name.clone())).into()),
+    };
+    let labels = list.labels.clone();
+    file(tdo, tracker, id, &repo, &labels)
+}
+
+fn file<T: IssueTracker + ?Sized>(tdo: &mut Tdo,
+                                  tracker: &mut T,
+                                  id: u32,
+                                  repo: &str,
+                                  labels: &[String])
+                                  -> TdoResult<RemoteIssue> {
+    let todo = tdo.get_todo_mut(id)?;
+    if todo.github.is_some() {
+        return Err(ErrorKind::GithubError(github_error::ErrorKind::AlreadyLinked(id)).into());
+    }
+    let issue = tracker.create_issue(repo, &todo.name, labels)?;
+    let link = RemoteIssue::with_forge(tracker.forge(), repo, issue.number);
+    todo.github = Some(link.clone());
+    info!("created issue {}#{} on {} for todo {}",
+          repo,
+          issue.number,
+          link.forge.as_str(),
+          id);
+    Ok(link)
+}
+
+/// Mark a linked todo as done and close its issue.
+///
+/// If the forge can not be reached, the todo is changed anyway and closing the issue is
+/// queued in the outbox (see the `outbox` module). Other failures leave the todo
+/// untouched. This function returns a `GithubError::NoIssueAsigned` if the todo is not
+/// linked to an issue of the tracker's forge.
+pub fn close<T: IssueTracker + ?Sized>(tdo: &mut Tdo, tracker: &mut T, id: u32) -> TdoResult<Delivery> {
+    let link = linked_issue(tdo, tracker, id)?;
+    let delivery = outbox::deliver(tdo, tracker, id, link, Operation::Close)?;
+    tdo.get_todo_mut(id)?.set_done();
+    Ok(delivery)
+}
+
+/// Mark a linked todo as undone and reopen its issue.
+///
+/// Failures are handled like in `close`.
+pub fn reopen<T: IssueTracker + ?Sized>(tdo: &mut Tdo, tracker: &mut T, id: u32) -> TdoResult<Delivery> {
+    let link = linked_issue(tdo, tracker, id)?;
+    let delivery = outbox::deliver(tdo, tracker, id, link, Operation::Reopen)?;
+    tdo.get_todo_mut(id)?.set_undone();
+    Ok(delivery)
+}
+
+/// Rename a linked todo and its issue.
+///
+/// Failures are handled like in `close`.
+pub fn retitle<T: IssueTracker + ?Sized>(tdo: &mut Tdo,
+                                         tracker: &mut T,
+                                         id: u32,
+                                         title: &str)
+                                         -> TdoResult<Delivery> {
+    let link = linked_issue(tdo, tracker, id)?;
+    let operation = Operation::Retitle {
+        from: tdo.get_todo_mut(id)?.name.clone(),
+        to: title.to_string(),
+    };
+    let delivery = outbox::deliver(tdo, tracker, id, link, operation)?;
+    tdo.get_todo_mut(id)?.edit(title);
+    Ok(delivery)
+}
+
+/// Comment on the issue of a linked todo.
+///
+/// Failures are handled like in `close`.
+pub fn comment<T: IssueTracker + ?Sized>(tdo: &mut Tdo,
+                                         tracker: &mut T,
+                                         id: u32,
+                                         body: &str)
+                                         -> TdoResult<Delivery> {
+    let link = linked_issue(tdo, tracker, id)?;
+    let delivery = outbox::deliver(tdo, tracker, id, link, Operation::Comment { body: body.to_string() })?;
+    tdo.get_todo_mut(id)?.comment(body);
+    Ok(delivery)
+}
+
+/// Copy the comments on the issue of a linked todo into its comment thread.
+///
+/// Comments that were mirrored before are skipped. Local comments that were sent to the
+/// issue with `comment` are matched by their text and take over the ID of the remote
+/// comment. The thread is kept in chronological order. Returns the number of comments
+/// added to the thread. This function returns a `GithubError::NoIssueAsigned` if the
+/// todo is not linked to an issue of the tracker's forge.
+pub fn mirror_comments<T: IssueTracker + ?Sized>(tdo: &mut Tdo, tracker: &mut T, id: u32) -> TdoResult<usize> {
+    let link = linked_issue(tdo, tracker, id)?;
+    let remote = tracker.comments(&link.repo, link.issue_number)?;
+    let todo = tdo.get_todo_mut(id)?;
+    let local = todo.comments.len();
+    let mut adopted = Vec::new();
+    let mut added = 0;
+    for comment in remote {
+        if comment.remote_id.is_some() && todo.comments.iter().any(|known| known.remote_id == comment.remote_id) {
+            continue;
+        }
+        // comments sent before their ID was known are matched by body, each one at most once
+        let matching = (0..local).find(|index| {
+            let known = &todo.comments[*index];
+            known.remote_id.is_none() && known.body == comment.body && !adopted.contains(index)
+        });
+        match matching {
+            Some(index) => {
+                adopted.push(index);
+                todo.comments[index].remote_id = comment.remote_id;
+                todo.comments[index].author = comment.author;
+            }
+            None => {
+                todo.comments.push(comment);
+                added += 1;
+            }
+        }
+    }
+    todo.comments.sort_by_key(|comment| comment.created);
+    info!("mirrored {} comments of {}#{} to todo {}",
+          added,
+          link.repo,
+          link.issue_number,
+          id);
+    Ok(added)
+}
+
+fn linked_issue<T: IssueTracker + ?Sized>(tdo: &mut Tdo, tracker: &T, id: u32) -> TdoResult<RemoteIssue> {
+    match tdo.get_todo_mut(id)?.github {
+        Some(ref link) if link.forge == tracker.forge() => Ok(link.clone()),
+        _ => Err(ErrorKind::GithubError(github_error::ErrorKind::NoIssueAsigned(id)).into()),
+    }
+}
@@ -0,0 +1,533 @@
+//! SQLite storage backend for large `Tdo` containers.
+//!
+//! Lists, todos and their linked issues, pull requests and commits are stored in separate tables. Saving only touches
+//! the rows that actually changed since the database was last written, so big containers
+//! do not have to be rewritten as a whole. Queued remote operations are kept as JSON
+//! entries of the `outbox` table.
+use std::collections::HashMap;
+use rusqlite::{Connection, OptionalExtension, Row, params};
+use rusqlite::types::Type;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json;
+use tdo::{MovePolicy, Tdo};
+use list::TodoList;
+use query::SavedSearch;
+use todo::{CacheValidators, Commit, Forge, PullRequest, PullState, RemoteIssue, Todo};
+use migration;
+use storage::{self, Storage};
+use error::*;
+
+const SCHEMA: &str = "
+    PRAGMA foreign_keys = ON;
+    CREATE TABLE IF NOT EXISTS meta (
+        key TEXT PRIMARY KEY,
+        value TEXT
+    );
+    CREATE TABLE IF NOT EXISTS lists (
+        name TEXT PRIMARY KEY,
+        position INTEGER NOT NULL,
+        repo TEXT,
+        forge TEXT NOT NULL DEFAULT 'github',
+        labels TEXT NOT NULL DEFAULT '[]'
+    );
+    CREATE TABLE IF NOT EXISTS todos (
+        id INTEGER PRIMARY KEY,
+        list TEXT NOT NULL REFERENCES lists(name) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        done INTEGER NOT NULL,
+        comments TEXT NOT NULL DEFAULT '[]',
+        activity TEXT NOT NULL DEFAULT '[]'
+    );
+    CREATE TABLE IF NOT EXISTS github (
+        todo_id INTEGER PRIMARY KEY REFERENCES todos(id) ON DELETE CASCADE,
+        repo TEXT NOT NULL,
+        issue_number INTEGER NOT NULL,
+        forge TEXT NOT NULL DEFAULT 'github',
+        etag TEXT,
+        last_modified TEXT
+    );
+    CREATE TABLE IF NOT EXISTS pull_requests (
+        todo_id INTEGER PRIMARY KEY REFERENCES todos(id) ON DELETE CASCADE,
+        repo TEXT NOT NULL,
+        number INTEGER NOT NULL,
+        state TEXT NOT NULL,
+        forge TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS commits (
+        todo_id INTEGER PRIMARY KEY REFERENCES todos(id) ON DELETE CASCADE,
+        repo TEXT NOT NULL,
+        sha TEXT NOT NULL,
+        forge TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS outbox (
+        position INTEGER PRIMARY KEY,
+        entry TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS searches (
+        position INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        query TEXT NOT NULL
+    );
+";
+
+const SELECT_TODOS: &str = "
+    SELECT t.id, t.list, t.position, t.name, t.done, t.comments, t.activity,
+           g.repo, g.issue_number, g.forge, g.etag, g.last_modified,
+           p.repo, p.number, p.state, p.forge,
+           c.repo, c.sha, c.forge
+    FROM todos t
+    LEFT JOIN github g ON g.todo_id = t.id
+    LEFT JOIN pull_requests p ON p.todo_id = t.id
+    LEFT JOIN commits c ON c.todo_id = t.id
+";
+
+/// A todo row as it is stored in the `todos` table, including its links.
+#[derive(PartialEq)]
+struct TodoRow {
+    list: String,
+    position: i64,
+    name: String,
+    done: bool,
+    // comment thread and activity log as JSON
+    comments: String,
+    activity: String,
+    github: Option<RemoteIssue>,
+    pull_request: Option<PullRequest>,
+    commit: Option<Commit>,
+}
+
+impl TodoRow {
+    fn from_row(row: &Row) -> ::rusqlite::Result<(u32, TodoRow)> {
+        Ok((row.get(0)?,
+            TodoRow {
+                list: row.get(1)?,
+                position: row.get(2)?,
+                name: row.get(3)?,
+                done: row.get(4)?,
+                comments: row.get(5)?,
+                activity: row.get(6)?,
+                github: link(row.get(7)?, row.get(8)?, row.get(9)?, row.get(10)?, row.get(11)?),
+                pull_request: pull_request(row.get(12)?, row.get(13)?, row.get(14)?, row.get(15)?),
+                commit: commit(row.get(16)?, row.get(17)?, row.get(18)?),
+            }))
+    }
+}
+
+/// Storage backend that keeps a `Tdo` container in a SQLite database.
+#[derive(Debug)]
+pub struct SqliteStorage {
+    conn: Connection,
+    path: String,
+}
+
+impl SqliteStorage {
+    /// Open (or create) the SQLite database at the given path.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tdo_core::sqlite::*;
+    /// let storage = SqliteStorage::open("foo.sqlite").unwrap();
+    /// ```
+    pub fn open(path: &str) -> TdoResult<SqliteStorage> {
+        match Connection::open(path) {
+            Ok(conn) => SqliteStorage::init(conn, path),
+            Err(e) => {
+                Err(Error::with_chain(e,
+                                      ErrorKind::StorageError(storage_error::ErrorKind::FileNotFound(path.to_string()))))
+            }
+        }
+    }
+
+    /// Create a database that only lives as long as the backend itself.
+    pub fn open_in_memory() -> TdoResult<SqliteStorage> {
+        match Connection::open_in_memory() {
+            Ok(conn) => SqliteStorage::init(conn, ":memory:"),
+            Err(e) => {
+                Err(Error::with_chain(e,
+                                      ErrorKind::StorageError(storage_error::ErrorKind::FileNotFound(":memory:".to_string()))))
+            }
+        }
+    }
+
+    fn init(conn: Connection, path: &str) -> TdoResult<SqliteStorage> {
+        match conn.execute_batch(SCHEMA).and_then(|_| SqliteStorage::upgrade(&conn)) {
+            Ok(_) => {
+                Ok(SqliteStorage {
+                    conn,
+                    path: path.to_string(),
+                })
+            }
+            Err(e) => {
+                Err(Error::with_chain(e,
+                                      ErrorKind::StorageError(storage_error::ErrorKind::FileCorrupted(path.to_string()))))
+            }
+        }
+    }
+
+    // Add the columns that databases written by earlier releases lack.
+    fn upgrade(conn: &Connection) -> ::rusqlite::Result<()> {
+        let columns = SqliteStorage::columns(conn, "lists")?;
+        if !columns.iter().any(|column| column == "repo") {
+            conn.execute("ALTER TABLE lists ADD COLUMN repo TEXT", [])?;
+        }
+        if !columns.iter().any(|column| column == "labels") {
+            conn.execute_batch("ALTER TABLE lists ADD COLUMN forge TEXT NOT NULL DEFAULT 'github';
+                                ALTER TABLE lists ADD COLUMN labels TEXT NOT NULL DEFAULT '[]';")?;
+        }
+
+        let columns = SqliteStorage::columns(conn, "todos")?;
+        if !columns.iter().any(|column| column == "comments") {
+            conn.execute_batch("ALTER TABLE todos ADD COLUMN comments TEXT NOT NULL DEFAULT '[]';
+                                ALTER TABLE todos ADD COLUMN activity TEXT NOT NULL DEFAULT '[]';")?;
+        }
+
+        let columns = SqliteStorage::columns(conn, "github")?;
+        if !columns.iter().any(|column| column == "forge") {
+            conn.execute("ALTER TABLE github ADD COLUMN forge TEXT NOT NULL DEFAULT 'github'", [])?;
+        }
+        if !columns.iter().any(|column| column == "etag") {
+            conn.execute_batch("ALTER TABLE github ADD COLUMN etag TEXT;
+                                ALTER TABLE github ADD COLUMN last_modified TEXT;")?;
+        }
+        Ok(())
+    }
+
+    fn columns(conn: &Connection, table: &str) -> ::rusqlite::Result<Vec<String>> {
+        let mut stmt = conn.prepare("SELECT name FROM pragma_table_info(?1)")?;
+        let columns = stmt.query_map([table], |row| row.get::<_, String>(0))?;
+        columns.collect()
+    }
+
+    /// Path of the underlying database, `:memory:` for in-memory databases.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn version(&self) -> ::rusqlite::Result<Option<String>> {
+        self.conn
+            .query_row("SELECT value FROM meta WHERE key = 'version'", [], |row| row.get(0))
+            .optional()
+    }
+
+    // Databases written by earlier releases are brought up to date by `open` and `read`,
+    // so the container is always returned in the current schema.
+    fn read(&self) -> ::rusqlite::Result<Option<Tdo>> {
+        if self.version()?.is_none() {
+            return Ok(None);
+        }
+        // databases written by tdo-core 0.2 contain the GitHub token
+        let access_token: Option<String> = self.conn
+            .query_row("SELECT value FROM meta WHERE key = 'access_token'", [], |row| row.get(0))
+            .optional()?
+            .and_then(|token| token);
+        let move_policy: Option<String> = self.conn
+            .query_row("SELECT value FROM meta WHERE key = 'move_policy'", [], |row| row.get(0))
+            .optional()?;
+        let move_policy = move_policy.and_then(|policy| MovePolicy::from_name(&policy)).unwrap_or_default();
+
+        let mut lists: Vec<TodoList> = vec![];
+        let mut stmt = self.conn.prepare("SELECT name, repo, forge, labels FROM lists ORDER BY position")?;
+        for row in stmt.query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?))
+            })? {
+            let (name, repo, forge, labels) = row?;
+            let mut list = TodoList::new(&name);
+            list.repo = repo;
+            list.forge = Forge::from_name(&forge).unwrap_or_default();
+            list.labels = from_json(3, &labels)?;
+            lists.push(list);
+        }
+
+        let mut stmt = self.conn.prepare(&format!("{} ORDER BY t.position", SELECT_TODOS))?;
+        for row in stmt.query_map([], TodoRow::from_row)? {
+            let (id, row) = row?;
+            let mut todo = Todo::new(id, &row.name, row.github);
+            todo.done = row.done;
+            todo.pull_request = row.pull_request;
+            todo.commit = row.commit;
+            todo.comments = from_json(5, &row.comments)?;
+            todo.activity = from_json(6, &row.activity)?;
+            let list_name = row.list;
+            if let Some(list) = lists.iter_mut().find(|list| list.name == list_name) {
+                list.add(todo);
+            }
+        }
+
+        let mut outbox = vec![];
+        let mut stmt = self.conn.prepare("SELECT entry FROM outbox ORDER BY position")?;
+        for entry in stmt.query_map([], |row| row.get::<_, String>(0))? {
+            let entry = entry?;
+            outbox.push(from_json(0, &entry)?);
+        }
+
+        let mut searches = vec![];
+        let mut stmt = self.conn.prepare("SELECT name, query FROM searches ORDER BY position")?;
+        for row in stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))? {
+            let (name, query) = row?;
+            match SavedSearch::new(&name, &query) {
+                Ok(search) => searches.push(search),
+                Err(e) => return Err(::rusqlite::Error::FromSqlConversionFailure(1, Type::Text, e.to_string().into())),
+            }
+        }
+
+        Ok(Some(Tdo {
+            lists,
+            move_policy,
+            outbox,
+            searches,
+            access_token,
+            rate_limit: None,
+            version: migration::SCHEMA_VERSION.to_string(),
+        }))
+    }
+
+    fn write(&mut self, tdo: &Tdo) -> ::rusqlite::Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            tx.execute("INSERT INTO meta (key, value) VALUES ('version', ?1)
+                        ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+                         params![tdo.version])?;
+            tx.execute("INSERT INTO meta (key, value) VALUES ('move_policy', ?1)
+                        ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+                         params![tdo.move_policy.as_str()])?;
+            // databases written by earlier releases contain the GitHub token
+            tx.execute("DELETE FROM meta WHERE key = 'access_token'", [])?;
+
+            // lists
+            let mut stored_lists: HashMap<String, (i64, Option<String>, String, String)> = HashMap::new();
+            {
+                let mut stmt = tx.prepare("SELECT name, position, repo, forge, labels FROM lists")?;
+                for row in stmt.query_map([], |row| {
+                        Ok((row.get(0)?, (row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)))
+                    })? {
+                    let (name, stored) = row?;
+                    stored_lists.insert(name, stored);
+                }
+            }
+            for (position, list) in tdo.lists.iter().enumerate() {
+                let labels = to_json(&list.labels)?;
+                let row = (position as i64, list.repo.clone(), list.forge.as_str().to_string(), labels);
+                if stored_lists.remove(&list.name) != Some(row.clone()) {
+                    tx.execute("INSERT INTO lists (name, position, repo, forge, labels) VALUES (?1, ?2, ?3, ?4, ?5)
+                                ON CONFLICT(name) DO UPDATE SET position = excluded.position,
+                                    repo = excluded.repo, forge = excluded.forge, labels = excluded.labels",
+                               params![list.name, row.0, row.1, row.2, row.3])?;
+                }
+            }
+
+            // todos and their links
+            let mut stored_todos: HashMap<u32, TodoRow> = HashMap::new();
+            {
+                let mut stmt = tx.prepare(SELECT_TODOS)?;
+                for row in stmt.query_map([], TodoRow::from_row)? {
+                    let (id, todo) = row?;
+                    stored_todos.insert(id, todo);
+                }
+            }
+            let mut position = 0;
+            for list in &tdo.lists {
+                for todo in &list.list {
+                    let row = TodoRow {
+                        list: list.name.clone(),
+                        position,
+                        name: todo.name.clone(),
+                        done: todo.done,
+                        comments: to_json(&todo.comments)?,
+                        activity: to_json(&todo.activity)?,
+                        github: todo.github.clone(),
+                        pull_request: todo.pull_request.clone(),
+                        commit: todo.commit.clone(),
+                    };
+                    position += 1;
+                    let stored = stored_todos.remove(&todo.id);
+                    if stored.as_ref() == Some(&row) {
+                        continue;
+                    }
+                    tx.execute("INSERT INTO todos (id, list, position, name, done, comments, activity)
+                                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                                ON CONFLICT(id) DO UPDATE SET list = excluded.list,
+                                    position = excluded.position, name = excluded.name,
+                                    done = excluded.done, comments = excluded.comments,
+                                    activity = excluded.activity",
+                               params![todo.id, row.list, row.position, row.name, row.done, row.comments, row.activity])?;
+                    let (github, pull_request, commit) = match stored {
+                        Some(stored) => (Some(stored.github), Some(stored.pull_request), Some(stored.commit)),
+                        None => (None, None, None),
+                    };
+                    if github != Some(row.github.clone()) {
+                        match row.github {
+                            Some(link) => {
+                                tx.execute("INSERT INTO github (todo_id, repo, issue_number, forge, etag, last_modified)
+                                            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                                            ON CONFLICT(todo_id) DO UPDATE SET
+                                                repo = excluded.repo,
+                                                issue_number = excluded.issue_number,
+                                                forge = excluded.forge,
+                                                etag = excluded.etag,
+                                                last_modified = excluded.last_modified",
+                                           params![todo.id,
+                                                   link.repo,
+                                                   link.issue_number,
+                                                   link.forge.as_str(),
+                                                   link.cache.etag,
+                                                   link.cache.last_modified])?;
+                            }
+                            None => {
+                                tx.execute("DELETE FROM github WHERE todo_id = ?1",
+                                           params![todo.id])?;
+                            }
+                        }
+                    }
+                    if pull_request != Some(row.pull_request.clone()) {
+                        match row.pull_request {
+                            Some(pull) => {
+                                tx.execute("INSERT INTO pull_requests (todo_id, repo, number, state, forge)
+                                            VALUES (?1, ?2, ?3, ?4, ?5)
+                                            ON CONFLICT(todo_id) DO UPDATE SET
+                                                repo = excluded.repo,
+                                                number = excluded.number,
+                                                state = excluded.state,
+                                                forge = excluded.forge",
+                                           params![todo.id,
+                                                   pull.repo,
+                                                   pull.number,
+                                                   pull.state.as_str(),
+                                                   pull.forge.as_str()])?;
+                            }
+                            None => {
+                                tx.execute("DELETE FROM pull_requests WHERE todo_id = ?1",
+                                           params![todo.id])?;
+                            }
+                        }
+                    }
+                    if commit != Some(row.commit.clone()) {
+                        match row.commit {
+                            Some(commit) => {
+                                tx.execute("INSERT INTO commits (todo_id, repo, sha, forge)
+                                            VALUES (?1, ?2, ?3, ?4)
+                                            ON CONFLICT(todo_id) DO UPDATE SET
+                                                repo = excluded.repo,
+                                                sha = excluded.sha,
+                                                forge = excluded.forge",
+                                           params![todo.id, commit.repo, commit.sha, commit.forge.as_str()])?;
+                            }
+                            None => {
+                                tx.execute("DELETE FROM commits WHERE todo_id = ?1",
+                                           params![todo.id])?;
+                            }
+                        }
+                    }
+                }
+            }
+
+            // the outbox and the saved searches are small, so they are always rewritten
+            tx.execute("DELETE FROM outbox", [])?;
+            for (position, pending) in tdo.outbox.iter().enumerate() {
+                let entry = to_json(pending)?;
+                tx.execute("INSERT INTO outbox (position, entry) VALUES (?1, ?2)",
+                           params![position as i64, entry])?;
+            }
+            tx.execute("DELETE FROM searches", [])?;
+            for (position, search) in tdo.searches.iter().enumerate() {
+                tx.execute("INSERT INTO searches (position, name, query) VALUES (?1, ?2, ?3)",
+                           params![position as i64, search.name, search.query.as_str()])?;
+            }
+
+            for id in stored_todos.keys() {
+                tx.execute("DELETE FROM todos WHERE id = ?1", params![id])?;
+            }
+            for name in stored_lists.keys() {
+                tx.execute("DELETE FROM lists WHERE name = ?1", params![name])?;
+            }
+        }
+        tx.commit()
+    }
+}
+
+// Build the issue link of a todo from the columns of the `github` table.
+fn link(repo: Option<String>,
+        issue: Option<u32>,
+        forge: Option<String>,
+        etag: Option<String>,
+        last_modified: Option<String>)
+        -> Option<RemoteIssue> {
+    let forge = forge.and_then(|forge| Forge::from_name(&forge)).unwrap_or_default();
+    match (repo, issue) {
+        (Some(repo), Some(issue)) => {
+            let mut link = RemoteIssue::with_forge(forge, &repo, issue);
+            link.cache = CacheValidators { etag, last_modified };
+            Some(link)
+        }
+        _ => None,
+    }
+}
+
+// Build the pull request link of a todo from the columns of the `pull_requests` table.
+fn pull_request(repo: Option<String>,
+                number: Option<u32>,
+                state: Option<String>,
+                forge: Option<String>)
+                -> Option<PullRequest> {
+    let forge = forge.and_then(|forge| Forge::from_name(&forge)).unwrap_or_default();
+    match (repo, number) {
+        (Some(repo), Some(number)) => {
+            let mut pull = PullRequest::with_forge(forge, &repo, number);
+            pull.state = state.and_then(|state| PullState::from_name(&state)).unwrap_or_default();
+            Some(pull)
+        }
+        _ => None,
+    }
+}
+
+// Read a column that holds JSON.
+fn from_json<T: DeserializeOwned>(column: usize, json: &str) -> ::rusqlite::Result<T> {
+    serde_json::from_str(json).map_err(|e| ::rusqlite::Error::FromSqlConversionFailure(column, Type::Text, Box::new(e)))
+}
+
+fn to_json<T: Serialize>(value: &T) -> ::rusqlite::Result<String> {
+    serde_json::to_string(value).map_err(|e| ::rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+// Build the commit link of a todo from the columns of the `commits` table.
+fn commit(repo: Option<String>, sha: Option<String>, forge: Option<String>) -> Option<Commit> {
+    let forge = forge.and_then(|forge| Forge::from_name(&forge)).unwrap_or_default();
+    match (repo, sha) {
+        (Some(repo), Some(sha)) => Some(Commit::with_forge(forge, &repo, &sha)),
+        _ => None,
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn load(&self) -> TdoResult<Tdo> {
+        if let Ok(Some(version)) = self.version() {
+            migration::check_version(&version)?;
+        }
+        match self.read() {
+            Ok(Some(tdo)) => Ok(tdo),
+            Ok(None) => Err(ErrorKind::StorageError(storage_error::ErrorKind::FileNotFound(self.path.clone())).into()),
+            Err(e) => {
+                Err(Error::with_chain(e,
+                                      ErrorKind::StorageError(storage_error::ErrorKind::FileCorrupted(self.path.clone()))))
+            }
+        }
+    }
+
+    fn save(&mut self, tdo: &Tdo) -> TdoResult<()> {
+        let mut ids: Vec<u32> = tdo.lists
+            .iter()
+            .flat_map(|list| list.list.iter().map(|todo| todo.id))
+            .collect();
+        ids.sort();
+        if let Some(pair) = ids.windows(2).find(|pair| pair[0] == pair[1]) {
+            return Err(ErrorKind::TodoError(todo_error::ErrorKind::IDAlreadyExists(pair[0])).into());
+        }
+
+        match self.write(tdo) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(storage::save_failure(&self.path, e)),
+        }
+    }
+}
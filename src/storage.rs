@@ -0,0 +1,383 @@
+//! Pluggable persistence for `Tdo` containers.
+//!
+//! Every backend implements the `Storage` trait, so applications embedding `tdo_core`
+//! can provide their own way of persisting todos. The crate ships with the classic
+//! JSON file format (`JsonFile`) and a volatile `MemoryStorage`, which is mostly useful
+//! for tests.
+//!
+//! With the `toml` and `yaml` features enabled, containers can also be stored as TOML or
+//! YAML files, which are easier to edit by hand. `open` picks the right backend based on
+//! the file extension.
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use serde_json::Value;
+use tdo::Tdo;
+use migration::{self, Migration, ConversionReport};
+use error::*;
+
+/// Common interface of all storage backends.
+pub trait Storage {
+    /// Load a `Tdo` container from the backend.
+    fn load(&self) -> TdoResult<Tdo>;
+
+    /// Persist the given `Tdo` container in the backend.
+    fn save(&mut self, tdo: &Tdo) -> TdoResult<()>;
+}
+
+/// Copy a `Tdo` container from one storage backend to another.
+///
+/// This can be used to convert between formats, e.g. from the JSON file format into a
+/// SQLite database and back.
+///
+/// # Example
+///
+/// ```
+/// # use tdo_core::storage::*;
+/// # use tdo_core::tdo::*;
+/// let source = MemoryStorage::with_tdo(Tdo::new());
+/// let mut target = MemoryStorage::new();
+/// convert(&source, &mut target).unwrap();
+/// ```
+pub fn convert<S, T>(source: &S, target: &mut T) -> TdoResult<()>
+    where S: Storage + ?Sized,
+          T: Storage + ?Sized
+{
+    let tdo = source.load()?;
+    target.save(&tdo)
+}
+
+/// Supported on-disk formats of `Tdo` containers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// The JSON format used by all tdo applications.
+    Json,
+    /// TOML, available with the `toml` feature.
+    Toml,
+    /// YAML, available with the `yaml` feature.
+    Yaml,
+}
+
+impl Format {
+    /// Determine the format of a file from its extension.
+    ///
+    /// Files with a `.toml` extension are TOML files, `.yaml` and `.yml` files are YAML files.
+    /// Everything else is treated as JSON.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tdo_core::storage::*;
+    /// assert_eq!(Format::from_path("list.yml"), Format::Yaml);
+    /// assert_eq!(Format::from_path("list.json"), Format::Json);
+    /// ```
+    pub fn from_path(path: &str) -> Format {
+        match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => Format::Toml,
+            Some(ext) if ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml") => {
+                Format::Yaml
+            }
+            _ => Format::Json,
+        }
+    }
+}
+
+/// Create the file storage backend matching the extension of the given path.
+///
+/// This function returns a `StorageError::UnsupportedFormat` if the format was not
+/// enabled at compile time.
+pub fn open(path: &str) -> TdoResult<Box<dyn Storage>> {
+    let format = Format::from_path(path);
+    debug!("opening {} as {:?} file", path, format);
+    match format {
+        Format::Json => Ok(Box::new(JsonFile::new(path))),
+        #[cfg(feature = "toml")]
+        Format::Toml => Ok(Box::new(TomlFile::new(path))),
+        #[cfg(feature = "yaml")]
+        Format::Yaml => Ok(Box::new(YamlFile::new(path))),
+        #[allow(unreachable_patterns)]
+        _ => Err(ErrorKind::StorageError(storage_error::ErrorKind::UnsupportedFormat(path.to_string())).into()),
+    }
+}
+
+/// Convert a `Tdo` file into another format.
+///
+/// The formats of both files are determined by their extensions (see `Format::from_path`).
+pub fn convert_file(source: &str, target: &str) -> TdoResult<()> {
+    convert(&*open(source)?, &mut *open(target)?)
+}
+
+/// Load a `Tdo` file and report which migration steps were applied to it.
+///
+/// Just like `open`, the format is determined by the file extension.
+/// Files written by newer releases are rejected with a `StorageError::UnsupportedVersion`.
+pub fn load_migrated(path: &str) -> TdoResult<(Tdo, Vec<Migration>)> {
+    load_file(path, Format::from_path(path))
+}
+
+/// Report what loading a `Tdo` file would convert, without writing anything.
+///
+/// See `migration::dry_run` for details.
+pub fn dry_run(path: &str) -> TdoResult<ConversionReport> {
+    migration::dry_run(read_value(path, Format::from_path(path))?)
+}
+
+fn load_file(path: &str, format: Format) -> TdoResult<(Tdo, Vec<Migration>)> {
+    from_value(path, read_value(path, format)?)
+}
+
+/// Migrate a raw container read from the given path and deserialize it.
+///
+/// A GitHub token stored by tdo-core 0.2 is kept as the session token of the container.
+pub(crate) fn from_value(path: &str, value: Value) -> TdoResult<(Tdo, Vec<Migration>)> {
+    let token = value.get("access_token").and_then(Value::as_str).map(str::to_string);
+    let migrated = migration::migrate(value)?;
+    match serde_json::from_value::<Tdo>(migrated.value) {
+        Ok(mut tdo) => {
+            tdo.access_token = token;
+            Ok((tdo, migrated.steps))
+        }
+        Err(e) => {
+            Err(Error::with_chain(e,
+                                  ErrorKind::StorageError(storage_error::ErrorKind::FileCorrupted(path.to_string()))))
+        }
+    }
+}
+
+/// Parse a file into its raw, not yet migrated representation.
+pub(crate) fn read_value(path: &str, format: Format) -> TdoResult<Value> {
+    let data = read_file(path)?;
+    match format {
+        Format::Json => {
+            serde_json::from_str(&data).map_err(|e| {
+                let (line, column) = (e.line(), e.column());
+                parse_error(path, line, column, e)
+            })
+        }
+        #[cfg(feature = "toml")]
+        Format::Toml => {
+            toml::from_str(&data).map_err(|e| {
+                let (line, column) = match e.span() {
+                    Some(span) => position(&data, span.start),
+                    None => (0, 0),
+                };
+                parse_error(path, line, column, e)
+            })
+        }
+        #[cfg(feature = "yaml")]
+        Format::Yaml => {
+            serde_yaml::from_str(&data).map_err(|e| {
+                let (line, column) = match e.location() {
+                    Some(location) => (location.line(), location.column()),
+                    None => (0, 0),
+                };
+                parse_error(path, line, column, e)
+            })
+        }
+        #[allow(unreachable_patterns)]
+        _ => Err(ErrorKind::StorageError(storage_error::ErrorKind::UnsupportedFormat(path.to_string())).into()),
+    }
+}
+
+fn parse_error<E>(path: &str, line: usize, column: usize, cause: E) -> Error
+    where E: ::std::error::Error + Send + 'static
+{
+    Error::with_chain(cause,
+                      ErrorKind::StorageError(storage_error::ErrorKind::ParseError(path.to_string(),
+                                                                                  line,
+                                                                                  column)))
+}
+
+/// Line and column (both starting at 1) of a byte offset in the given text.
+#[cfg(feature = "toml")]
+fn position(data: &str, offset: usize) -> (usize, usize) {
+    let before = &data[..offset.min(data.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|&c| c != '\n').count() + 1;
+    (line, column)
+}
+
+/// Storage backend for the JSON file format used by all tdo applications.
+///
+/// Older file formats are converted automatically when loading.
+#[derive(Debug, Clone)]
+pub struct JsonFile {
+    path: String,
+}
+
+impl JsonFile {
+    /// Create a backend for the JSON file at the given path.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tdo_core::storage::*;
+    /// let storage = JsonFile::new("foo.json");
+    /// ```
+    pub fn new(path: &str) -> JsonFile {
+        JsonFile { path: path.to_string() }
+    }
+
+    /// Path of the underlying JSON file.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl Storage for JsonFile {
+    fn load(&self) -> TdoResult<Tdo> {
+        load_file(&self.path, Format::Json).map(|(tdo, _)| tdo)
+    }
+
+    fn save(&mut self, tdo: &Tdo) -> TdoResult<()> {
+        match serde_json::to_string_pretty(tdo) {
+            Ok(data) => write_file(&self.path, &data),
+            Err(e) => Err(save_failure(&self.path, e)),
+        }
+    }
+}
+
+/// Volatile storage backend that keeps a copy of the `Tdo` container in memory.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage {
+    tdo: Option<Tdo>,
+}
+
+impl MemoryStorage {
+    /// Create an empty in-memory backend.
+    ///
+    /// Loading from an empty backend fails with a `StorageError::FileNotFound`.
+    pub fn new() -> MemoryStorage {
+        MemoryStorage { tdo: None }
+    }
+
+    /// Create an in-memory backend that already holds the given container.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tdo_core::storage::*;
+    /// # use tdo_core::tdo::*;
+    /// let storage = MemoryStorage::with_tdo(Tdo::new());
+    /// assert_eq!(storage.load().unwrap().lists.len(), 1);
+    /// ```
+    pub fn with_tdo(tdo: Tdo) -> MemoryStorage {
+        MemoryStorage { tdo: Some(tdo) }
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn load(&self) -> TdoResult<Tdo> {
+        match self.tdo {
+            Some(ref tdo) => Ok(tdo.clone()),
+            None => Err(ErrorKind::StorageError(storage_error::ErrorKind::FileNotFound(":memory:".to_string())).into()),
+        }
+    }
+
+    fn save(&mut self, tdo: &Tdo) -> TdoResult<()> {
+        self.tdo = Some(tdo.clone());
+        Ok(())
+    }
+}
+
+fn read_file(path: &str) -> TdoResult<String> {
+    let mut data = String::new();
+    match File::open(path) {
+        Ok(mut file) => {
+            match file.read_to_string(&mut data) {
+                Ok(_) => Ok(data),
+                Err(e) => {
+                    Err(Error::with_chain(e,
+                                          ErrorKind::StorageError(storage_error::ErrorKind::FileCorrupted(path.to_string()))))
+                }
+            }
+        }
+        Err(e) => {
+            Err(Error::with_chain(e,
+                                  ErrorKind::StorageError(storage_error::ErrorKind::FileNotFound(path.to_string()))))
+        }
+    }
+}
+
+fn write_file(path: &str, data: &str) -> TdoResult<()> {
+    match File::create(path).and_then(|mut f| f.write_all(data.as_bytes())) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(save_failure(path, e)),
+    }
+}
+
+/// A `StorageError::SaveFailure` for the given path, caused by `cause`.
+pub(crate) fn save_failure<E>(path: &str, cause: E) -> Error
+    where E: ::std::error::Error + Send + 'static
+{
+    Error::with_chain(cause,
+                      ErrorKind::StorageError(storage_error::ErrorKind::SaveFailure(path.to_string())))
+}
+
+/// Storage backend for hand-editable TOML files.
+#[cfg(feature = "toml")]
+#[derive(Debug, Clone)]
+pub struct TomlFile {
+    path: String,
+}
+
+#[cfg(feature = "toml")]
+impl TomlFile {
+    /// Create a backend for the TOML file at the given path.
+    pub fn new(path: &str) -> TomlFile {
+        TomlFile { path: path.to_string() }
+    }
+
+    /// Path of the underlying TOML file.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+#[cfg(feature = "toml")]
+impl Storage for TomlFile {
+    fn load(&self) -> TdoResult<Tdo> {
+        load_file(&self.path, Format::Toml).map(|(tdo, _)| tdo)
+    }
+
+    fn save(&mut self, tdo: &Tdo) -> TdoResult<()> {
+        match toml::to_string_pretty(tdo) {
+            Ok(data) => write_file(&self.path, &data),
+            Err(e) => Err(save_failure(&self.path, e)),
+        }
+    }
+}
+
+/// Storage backend for hand-editable YAML files.
+#[cfg(feature = "yaml")]
+#[derive(Debug, Clone)]
+pub struct YamlFile {
+    path: String,
+}
+
+#[cfg(feature = "yaml")]
+impl YamlFile {
+    /// Create a backend for the YAML file at the given path.
+    pub fn new(path: &str) -> YamlFile {
+        YamlFile { path: path.to_string() }
+    }
+
+    /// Path of the underlying YAML file.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+#[cfg(feature = "yaml")]
+impl Storage for YamlFile {
+    fn load(&self) -> TdoResult<Tdo> {
+        load_file(&self.path, Format::Yaml).map(|(tdo, _)| tdo)
+    }
+
+    fn save(&mut self, tdo: &Tdo) -> TdoResult<()> {
+        match serde_yaml::to_string(tdo) {
+            Ok(data) => write_file(&self.path, &data),
+            Err(e) => Err(save_failure(&self.path, e)),
+        }
+    }
+}
@@ -1,10 +1,18 @@
 //! General implementation of tdos base structure.
-use json::parse;
-use std::fs::File;
-use std::io::{Read, Write, stdout, stdin};
-use list::TodoList;
-use legacy::*;
-use todo::Todo;
+use list::{ListView, TodoList};
+use migration;
+use credentials::{self, Credentials, TokenProvider};
+use github::GitHubClient;
+use http::{HttpClient, RateLimit};
+use outbox::{self, Delivery, FlushReport, PendingOperation, RetryPolicy};
+use query::{Query, SavedSearch};
+use remote::{self, ImportReport, IssueFilter, IssueTracker, SyncReport};
+use storage;
+#[cfg(feature = "encryption")]
+use storage::Storage;
+#[cfg(feature = "encryption")]
+use encryption::EncryptedFile;
+use todo::{ActivityKind, GitHub, Todo};
 use error::*;
 
 /// Basic container structure for a set of todo lists.
@@ -18,10 +26,77 @@ use error::*;
 pub struct Tdo {
     /// A vector of all todo lists.
     pub lists: Vec<TodoList>,
-    //The Github API token.
-    access_token: Option<String>,
+    /// Which todos linked to issues may be moved between lists.
+    #[serde(default, skip_serializing_if = "MovePolicy::is_locked")]
+    pub move_policy: MovePolicy,
+    /// Remote operations that could not be sent yet (see the `outbox` module).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outbox: Vec<PendingOperation>,
+    /// Saved searches, shown as virtual lists by `views`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub searches: Vec<SavedSearch>,
+    // The Github API token of the current session. It is never written to disk,
+    // use the `credentials` module to persist it.
+    #[serde(skip)]
+    pub(crate) access_token: Option<String>,
+    // The GitHub rate limit reported with the last response of the current session.
+    #[serde(skip)]
+    pub(crate) rate_limit: Option<RateLimit>,
     // The tdo version the last dump was saved with.
-    version: String,
+    pub(crate) version: String,
+}
+
+fn list_view(list: &TodoList) -> ListView<'_> {
+    ListView {
+        name: &list.name,
+        todos: list.list.iter().collect(),
+        query: None,
+    }
+}
+
+/// Rules for moving todos that are linked to an issue.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MovePolicy {
+    /// Linked todos can not be moved, like in earlier releases.
+    #[default]
+    Locked,
+    /// Linked todos can be moved to lists that track the repository of their issue on the
+    /// same forge.
+    RepoBound,
+    /// Linked todos can be moved like any other todo.
+    Free,
+}
+
+impl MovePolicy {
+    /// Name of the policy, as used in stored files.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            MovePolicy::Locked => "locked",
+            MovePolicy::RepoBound => "repo_bound",
+            MovePolicy::Free => "free",
+        }
+    }
+
+    /// Look up a policy by its name.
+    pub fn from_name(name: &str) -> Option<MovePolicy> {
+        match name {
+            "locked" => Some(MovePolicy::Locked),
+            "repo_bound" => Some(MovePolicy::RepoBound),
+            "free" => Some(MovePolicy::Free),
+            _ => None,
+        }
+    }
+
+    fn is_locked(&self) -> bool {
+        *self == MovePolicy::Locked
+    }
+}
+
+impl Default for Tdo {
+    fn default() -> Tdo {
+        Tdo::new()
+    }
 }
 
 impl Tdo {
@@ -37,15 +112,22 @@ impl Tdo {
     pub fn new() -> Tdo {
         Tdo {
             lists: vec![TodoList::default()],
+            move_policy: MovePolicy::default(),
+            outbox: vec![],
+            searches: vec![],
             access_token: None,
-            version: env!("CARGO_PKG_VERSION").to_string(),
+            rate_limit: None,
+            version: migration::SCHEMA_VERSION.to_string(),
         }
     }
 
-    /// Load a saved `Tdo` container from a JSON file.
+    /// Load a saved `Tdo` container from a file.
     ///
     /// This function returns a `ResultType` which will yield the
-    /// deserialized JSON or a `serde_json::Error`.
+    /// deserialized container or a `StorageError`. Files written by older
+    /// releases are migrated automatically (see the `migration` module).
+    /// The file format is picked by the file extension (see `storage::open`),
+    /// files without a known extension are read as JSON.
     ///
     /// # Example
     ///
@@ -54,22 +136,14 @@ impl Tdo {
     /// let mut tdo = Tdo::load("foo.json");
     /// ```
     pub fn load(path: &str) -> TdoResult<Tdo> {
-        match File::open(path) {
-            Ok(file) => {
-                match super::serde_json::from_reader(&file) {
-                    Ok(tdo) => Ok(tdo),
-                    Err(_) => update_json(path),
-                }
-            }
-            Err(_) => Err(ErrorKind::StorageError(storage_error::ErrorKind::FileNotFound).into()),
-        }
-
+        storage::open(path)?.load()
     }
 
-    /// Dump the `Tdo` container to a JSON file.
+    /// Dump the `Tdo` container to a file.
     ///
     /// This function returns a `ResultType` yielding a `StorageError::SaveFailure`
-    /// if the JSON file could not be opened/saved.
+    /// if the file could not be opened/saved.
+    /// Just like `load`, the file format is picked by the file extension.
     ///
     /// # Example
     ///
@@ -80,32 +154,49 @@ impl Tdo {
     /// assert_eq!(res.unwrap(), ());
     /// ```
     pub fn save(&self, path: &str) -> TdoResult<()> {
-        // TODO: At this point we could be much more precise about the error if we would include
-        // the error from the file system as SaveFailure(ArbitraryErrorFromFS)
-        //  -- Feliix42 (2017-03-14; 17:04)
-        match File::create(path) {
-            Ok(mut f) => {
-                let _ = super::serde_json::to_writer_pretty(&mut f, self);
-                Ok(())
-            }
-            Err(_) => Err(ErrorKind::StorageError(storage_error::ErrorKind::SaveFailure).into()),
-        }
+        storage::open(path)?.save(self)
+    }
+
+    /// Load a `Tdo` container from a passphrase-protected file.
+    ///
+    /// This function returns a `StorageError::WrongPassphrase` if the passphrase does not
+    /// match the one the file was saved with.
+    #[cfg(feature = "encryption")]
+    pub fn load_encrypted(path: &str, passphrase: &str) -> TdoResult<Tdo> {
+        EncryptedFile::new(path, passphrase).load()
+    }
+
+    /// Dump the `Tdo` container to a file encrypted with the given passphrase.
+    #[cfg(feature = "encryption")]
+    pub fn save_encrypted(&self, path: &str, passphrase: &str) -> TdoResult<()> {
+        EncryptedFile::new(path, passphrase).save(self)
+    }
+
+    /// Sets the GitHub access token for the current session.
+    ///
+    /// The token is not saved together with the container.
+    /// Use a `credentials::Credentials` store to persist it.
+    pub fn set_gh_token(&mut self, token: &str) {
+        self.access_token = Some(token.to_string());
     }
 
-    /// Sets the GitHub access token.
-    pub fn set_gh_token(&mut self, token: Option<&str>) {
-        let gh_token = match token {
-            Some(x) => x.to_string(),
-            None => {
-                print!("Please generate an access token ({})\nand enter a valid accesstoken: ",
-                       "https://github.com/settings/tokens/new?scopes=repo&description=tdolist");
-                stdout().flush().ok().expect("Could not flush stdout!");
-                let mut answer = String::new();
-                stdin().read_line(&mut answer).unwrap();
-                answer.trim().to_string()
+    /// Obtain the GitHub access token for the current session.
+    ///
+    /// The token is looked up in the credentials store first. If there is none, the
+    /// frontend is asked through the given `TokenProvider` and the answer is kept in the
+    /// store (unless the store is read-only).
+    /// This function returns a `GithubError::MissingToken` if no token could be obtained.
+    pub fn obtain_gh_token<C, P>(&mut self, store: &mut C, provider: &mut P) -> TdoResult<()>
+        where C: Credentials + ?Sized,
+              P: TokenProvider + ?Sized
+    {
+        match credentials::obtain_token(store, provider, credentials::GITHUB)? {
+            Some(token) => {
+                self.access_token = Some(token);
+                Ok(())
             }
-        };
-        self.access_token = Some(gh_token);
+            None => Err(ErrorKind::GithubError(github_error::ErrorKind::MissingToken).into()),
+        }
     }
 
     /// Returns an Option<String> of the private access_token field.
@@ -113,15 +204,186 @@ impl Tdo {
         self.access_token.to_owned()
     }
 
+    /// Synchronize all todos linked to GitHub issues with the current issue state,
+    /// using the access token of the current session (see `remote::sync`).
+    ///
+    /// This function returns a `GithubError::MissingToken` if no token is set.
+    pub fn sync_github<H: HttpClient>(&mut self, http: H) -> TdoResult<SyncReport> {
+        self.with_github(http, remote::sync)
+    }
+
+    /// Import the open issues of a repository into a list, using the access token of the
+    /// current session (see `remote::import`).
+    ///
+    /// This function returns a `GithubError::MissingToken` if no token is set.
+    pub fn import_github<H: HttpClient>(&mut self,
+                                        http: H,
+                                        repo: &str,
+                                        list_name: &str,
+                                        filter: &IssueFilter)
+                                        -> TdoResult<ImportReport> {
+        self.with_github(http, |tdo, client| remote::import(tdo, client, repo, list_name, filter))
+    }
+
+    /// Create a GitHub issue for a local todo and link the todo to it, using the access
+    /// token of the current session (see `remote::promote`).
+    pub fn promote_github<H: HttpClient>(&mut self, http: H, id: u32, repo: &str) -> TdoResult<GitHub> {
+        self.with_github(http, |tdo, client| remote::promote(tdo, client, id, repo))
+    }
+
+    /// Create a GitHub issue for a local todo in the repository its list tracks, using the
+    /// access token of the current session (see `remote::publish`).
+    pub fn publish_github<H: HttpClient>(&mut self, http: H, id: u32) -> TdoResult<GitHub> {
+        self.with_github(http, |tdo, client| remote::publish(tdo, client, id))
+    }
+
+    /// Mark a linked todo as done and close its GitHub issue, using the access token of the
+    /// current session (see `remote::close`). Use `done_id` to leave the issue open.
+    pub fn close_github<H: HttpClient>(&mut self, http: H, id: u32) -> TdoResult<Delivery> {
+        self.with_github(http, |tdo, client| remote::close(tdo, client, id))
+    }
+
+    /// Mark a todo as done and, if `close` is set, close the GitHub issue it is linked to
+    /// (see `close_github`).
+    ///
+    /// Todos without a linked issue are marked as done without talking to GitHub.
+    /// The delivery of the close operation is returned if one was attempted.
+    pub fn done_id_with<H: HttpClient>(&mut self,
+                                       http: H,
+                                       id: u32,
+                                       close: bool)
+                                       -> TdoResult<Option<Delivery>> {
+        if close && self.get_todo_mut(id)?.github.is_some() {
+            self.close_github(http, id).map(Some)
+        } else {
+            self.done_id(id).map(|_| None)
+        }
+    }
+
+    /// Mark a linked todo as undone and reopen its GitHub issue, using the access token of
+    /// the current session (see `remote::reopen`).
+    pub fn reopen_github<H: HttpClient>(&mut self, http: H, id: u32) -> TdoResult<Delivery> {
+        self.with_github(http, |tdo, client| remote::reopen(tdo, client, id))
+    }
+
+    /// Rename a linked todo and its GitHub issue, using the access token of the current
+    /// session (see `remote::retitle`).
+    pub fn retitle_github<H: HttpClient>(&mut self, http: H, id: u32, title: &str) -> TdoResult<Delivery> {
+        self.with_github(http, |tdo, client| remote::retitle(tdo, client, id, title))
+    }
+
+    /// Comment on the GitHub issue of a linked todo, using the access token of the current
+    /// session (see `remote::comment`).
+    pub fn comment_github<H: HttpClient>(&mut self, http: H, id: u32, body: &str) -> TdoResult<Delivery> {
+        self.with_github(http, |tdo, client| remote::comment(tdo, client, id, body))
+    }
+
+    /// Copy the comments on the GitHub issue of a linked todo into its comment thread,
+    /// using the access token of the current session (see `remote::mirror_comments`).
+    pub fn mirror_comments_github<H: HttpClient>(&mut self, http: H, id: u32) -> TdoResult<usize> {
+        self.with_github(http, |tdo, client| remote::mirror_comments(tdo, client, id))
+    }
+
+    /// Replay the queued operations for GitHub issues, using the access token of the
+    /// current session (see `outbox::flush`).
+    pub fn flush_github<H: HttpClient>(&mut self, http: H, policy: &RetryPolicy) -> TdoResult<FlushReport> {
+        self.with_github(http, |tdo, client| outbox::flush(tdo, client, policy))
+    }
+
+    /// Rate limit of the GitHub API as reported with the last response of this session.
+    pub fn github_rate_limit(&self) -> Option<RateLimit> {
+        self.rate_limit
+    }
+
+    // Run a remote operation with a GitHub client for the session token and remember
+    // the rate limit it reported.
+    fn with_github<H, T, F>(&mut self, http: H, operation: F) -> TdoResult<T>
+        where H: HttpClient,
+              F: FnOnce(&mut Tdo, &mut GitHubClient<H>) -> TdoResult<T>
+    {
+        let mut client = match self.access_token {
+            Some(ref token) => GitHubClient::new(http, token),
+            None => return Err(ErrorKind::GithubError(github_error::ErrorKind::MissingToken).into()),
+        };
+        let result = operation(self, &mut client);
+        if let Some(rate_limit) = client.rate_limit() {
+            self.rate_limit = Some(rate_limit);
+        }
+        result
+    }
+
     /// Add a todo list to the container.
     pub fn add_list(&mut self, list: TodoList) -> TdoResult<()> {
-        match self.get_list_index(&list.name) {
-            Ok(_) => Err(ErrorKind::TodoError(todo_error::ErrorKind::NameAlreadyExists).into()),
-            Err(_) => {
-                self.lists.push(list);
-                Ok(())
-            }
+        if self.name_taken(&list.name) {
+            return Err(ErrorKind::TodoError(todo_error::ErrorKind::NameAlreadyExists(list.name)).into());
+        }
+        self.lists.push(list);
+        Ok(())
+    }
+
+    /// Save a search in the container.
+    ///
+    /// This function returns a `TodoError::NameAlreadyExists` if there already is a list or
+    /// a search with the same name.
+    pub fn add_search(&mut self, search: SavedSearch) -> TdoResult<()> {
+        if self.name_taken(&search.name) {
+            return Err(ErrorKind::TodoError(todo_error::ErrorKind::NameAlreadyExists(search.name)).into());
+        }
+        self.searches.push(search);
+        Ok(())
+    }
+
+    /// Remove a saved search from the container.
+    ///
+    /// This function returns a `TodoError::NoSuchList` if there is no search with the
+    /// given name.
+    pub fn remove_search(&mut self, name: &str) -> TdoResult<SavedSearch> {
+        match self.searches.iter().position(|search| search.name.to_lowercase() == name.to_lowercase()) {
+            Some(index) => Ok(self.searches.remove(index)),
+            None => Err(ErrorKind::TodoError(todo_error::ErrorKind::NoSuchList(name.to_string())).into()),
+        }
+    }
+
+    /// All todo lists followed by all saved searches, with the todos they show.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tdo_core::{query, tdo};
+    /// let mut tdo = tdo::Tdo::new();
+    /// tdo.add_search(query::SavedSearch::new("open", "!done").unwrap()).unwrap();
+    /// let names: Vec<&str> = tdo.views().iter().map(|view| view.name).collect();
+    /// assert_eq!(names, vec!["default", "open"]);
+    /// ```
+    pub fn views(&self) -> Vec<ListView<'_>> {
+        let lists = self.lists.iter().map(list_view);
+        let searches = self.searches.iter().map(|search| self.search_view(search));
+        lists.chain(searches).collect()
+    }
+
+    /// A single todo list or saved search, identified by its name.
+    ///
+    /// This function returns a `TodoError::NoSuchList` if there is neither a list nor a
+    /// search with the given name.
+    pub fn view(&self, name: &str) -> TdoResult<ListView<'_>> {
+        if let Some(search) = self.searches.iter().find(|search| search.name.to_lowercase() == name.to_lowercase()) {
+            return Ok(self.search_view(search));
         }
+        Ok(list_view(&self.lists[self.get_list_index(name)?]))
+    }
+
+    fn search_view<'a>(&'a self, search: &'a SavedSearch) -> ListView<'a> {
+        ListView {
+            name: &search.name,
+            todos: search.query.run(self).into_iter().map(|(_, todo)| todo).collect(),
+            query: Some(&search.query),
+        }
+    }
+
+    fn name_taken(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        self.lists.iter().any(|list| list.name.to_lowercase() == name) ||
+        self.searches.iter().any(|search| search.name.to_lowercase() == name)
     }
 
     /// Removes a list from the container.
@@ -129,28 +391,21 @@ impl Tdo {
         if list_name == "default" {
             Err(ErrorKind::TodoError(todo_error::ErrorKind::CanNotRemoveDefault).into())
         } else {
-            match self.get_list_index(list_name) {
-                Ok(index) => {
-                    self.lists.remove(index);
-                    Ok(())
-                }
-                Err(_) => Err(ErrorKind::TodoError(todo_error::ErrorKind::NoSuchList).into()),
-            }
+            let index = self.get_list_index(list_name)?;
+            self.lists.remove(index);
+            Ok(())
         }
     }
 
     /// Add a todo to the todo list, identified by its name.
     ///
     /// This function returns a `ResultType` with a `TodoError::NoSuchList`
-    /// if there is no matching list found.
+    /// if there is no matching list found and a `TodoError::VirtualList` if the name
+    /// belongs to a saved search.
     pub fn add_todo(&mut self, list_name: Option<&str>, todo: Todo) -> TdoResult<()> {
-        match self.get_list_index(&list_name.unwrap_or("default")) {
-            Ok(index) => {
-                self.lists[index].add(todo);
-                Ok(())
-            }
-            Err(x) => Err(x),
-        }
+        let index = self.get_list_index(list_name.unwrap_or("default"))?;
+        self.lists[index].add(todo);
+        Ok(())
     }
 
     /// Cycle through all todo lists and find the list which contains the todo with the given ID
@@ -163,27 +418,28 @@ impl Tdo {
                 return Ok(list);
             }
         }
-        Err(ErrorKind::TodoError(todo_error::ErrorKind::NotInList).into())
+        Err(ErrorKind::TodoError(todo_error::ErrorKind::NotInList(id)).into())
     }
     /// Cycle through all todo lists and mark a todo with the given ID as done.
     /// This function has no return value and thus won't indicate whether
     /// there was a matching todo found.
+    /// A linked GitHub issue stays open; use `done_id_with` to close it as well.
     pub fn done_id(&mut self, id: u32) -> TdoResult<()> {
-        let list = match self.find_id(id) {
-            Ok(list_id) => list_id,
-            Err(e) => return Err(e),
-        };
+        let list = self.find_id(id)?;
         self.lists[list].done_id(id)
     }
 
+    /// Cycle through all todo lists and mark a todo with the given ID as _undone_.
+    pub fn undone_id(&mut self, id: u32) -> TdoResult<()> {
+        let list = self.find_id(id)?;
+        self.lists[list].undone_id(id)
+    }
+
     /// Cycle through all todo lists and remove a todo with the given id.
     /// This function has no return value and thus won't indicate whether
     /// there was a matching todo found.
     pub fn remove_id(&mut self, id: u32) -> TdoResult<()> {
-        let list = match self.find_id(id) {
-            Ok(list_id) => list_id,
-            Err(e) => return Err(e),
-        };
+        let list = self.find_id(id)?;
         match self.lists[list].remove_id(id) {
             Err(e) => Err(e),
             _ => Ok(()),
@@ -199,20 +455,27 @@ impl Tdo {
 
     /// Remove all todos that have been marked as _done_ from a given todo list.
     pub fn clean_list(&mut self, list: &str) -> TdoResult<()> {
-        let index = match self.get_list_index(list) {
-            Ok(index) => index,
-            Err(e) => return Err(e),
-        };
+        let index = self.get_list_index(list)?;
         self.lists[index].clean();
         Ok(())
     }
 
-    fn get_list_index(&self, name: &str) -> TdoResult<usize> {
+    pub(crate) fn get_todo_mut(&mut self, id: u32) -> TdoResult<&mut Todo> {
+        let list = self.find_id(id)?;
+        let index = self.lists[list].contains_id(id)?;
+        Ok(&mut self.lists[list].list[index])
+    }
+
+    // Saved searches are reported as such, so they are never mistaken for missing lists.
+    pub(crate) fn get_list_index(&self, name: &str) -> TdoResult<usize> {
         match self.lists
             .iter()
             .position(|x| x.name.to_lowercase() == name.to_string().to_lowercase()) {
             Some(index) => Ok(index),
-            None => Err(ErrorKind::TodoError(todo_error::ErrorKind::NoSuchList).into()),
+            None if self.name_taken(name) => {
+                Err(ErrorKind::TodoError(todo_error::ErrorKind::VirtualList(name.to_string())).into())
+            }
+            None => Err(ErrorKind::TodoError(todo_error::ErrorKind::NoSuchList(name.to_string())).into()),
         }
     }
 
@@ -220,77 +483,52 @@ impl Tdo {
     pub fn get_highest_id(&self) -> u32 {
         self.lists
             .iter()
-            .fold(0, |acc, &ref x| {
+            .fold(0, |acc, x| {
                 x.list
                     .iter()
                     .fold(acc,
-                          |inner_acc, &ref y| if inner_acc < y.id { y.id } else { inner_acc })
+                          |inner_acc, y| if inner_acc < y.id { y.id } else { inner_acc })
             })
     }
 
+    /// Find all todos matching a query, along with their lists (see the `query` module).
+    ///
+    /// This function returns a `QueryError` if the query can not be parsed.
+    pub fn search(&self, query: &str) -> TdoResult<Vec<(&TodoList, &Todo)>> {
+        Ok(Query::parse(query)?.run(self))
+    }
+
     /// Move a `todo` between two lists.
+    ///
+    /// Whether todos linked to an issue may be moved is decided by the `move_policy` of
+    /// the container. This function returns a `GithubError::NotAllowedToMove` if the policy
+    /// forbids the move and a `TodoError::VirtualList` if the target is a saved search.
     pub fn move_todo(&mut self, id: u32, target_list: &str) -> TdoResult<()> {
         let src_index = self.find_id(id)?;
         let target = self.get_list_index(target_list)?;
 
-        //Check if todo is a github Issue
         let list_index = self.lists[src_index].contains_id(id)?;
-        if let Some(_) = self.lists[src_index].list[list_index].github {
-            return Err(ErrorKind::GithubError(github_error::ErrorKind::NotAllowedToMove).into())
-        }
-        let todo = self.lists[src_index].pop_id(id)?;
-        self.lists[target].insert_todo(todo);
-        Ok(())
-    }
-}
-
-fn update_json(path: &str) -> TdoResult<Tdo> {
-    match Tdo01::load(path) {
-        Ok(tdo) => Ok(tdo.into()),
-        Err(_) => {
-            println!("I have to do this here");
-            let mut file = File::open(path).unwrap();
-            let mut data = String::new();
-            file.read_to_string(&mut data).unwrap();
-            let mut json = match parse(&data) {
-                Ok(content) => content,
-                Err(_) => {
-                    return Err(ErrorKind::StorageError(storage_error::ErrorKind::FileCorrupted)
-                        .into())
+        if let Some(ref github) = self.lists[src_index].list[list_index].github {
+            let allowed = match self.move_policy {
+                MovePolicy::Locked => false,
+                MovePolicy::RepoBound => {
+                    let list = &self.lists[target];
+                    list.forge == github.forge && list.repo.as_ref() == Some(&github.repo)
                 }
+                MovePolicy::Free => true,
             };
-
-            let mut lists: Vec<TodoList> = vec![];
-
-            for outer in json.entries_mut() {
-                let mut list = TodoList::new(outer.0);
-                for inner in outer.1.entries_mut() {
-                    let tdo_id = match inner.0.parse::<u32>() {
-                        Ok(id) => id,
-                        Err(_) => return Err(ErrorKind::StorageError(storage_error::ErrorKind::UnableToConvert).into()),
-                    };
-                    let done = match inner.1.pop().as_bool() {
-                        Some(x) => x,
-                        None => return Err(ErrorKind::StorageError(storage_error::ErrorKind::UnableToConvert).into()),
-                    };
-                    let tdo_name = match inner.1.pop().as_str() {
-                        Some(x) => String::from(x),
-                        None => return Err(ErrorKind::StorageError(storage_error::ErrorKind::UnableToConvert).into()),
-                    };
-                    let mut todo = Todo::new(tdo_id, &tdo_name, None);
-                    if done {
-                        todo.set_done();
-                    }
-                    list.add(todo);
-                }
-                lists.push(list);
+            if !allowed {
+                return Err(ErrorKind::GithubError(github_error::ErrorKind::NotAllowedToMove(id)).into());
             }
-            let tdo = Tdo {
-                lists: lists,
-                access_token: None,
-                version: env!("CARGO_PKG_VERSION").to_string(),
-            };
-            Ok(tdo)
         }
+        let mut todo = self.lists[src_index].pop_id(id)?;
+        if src_index != target {
+            todo.record(ActivityKind::Moved {
+                from: self.lists[src_index].name.clone(),
+                to: self.lists[target].name.clone(),
+            });
+        }
+        self.lists[target].insert_todo(todo);
+        Ok(())
     }
 }
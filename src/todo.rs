@@ -1,4 +1,5 @@
 //! Implementation of a single Todo item.
+use std::time::SystemTime;
 
 /// Data Structure for a simple todo.
 ///
@@ -14,8 +15,21 @@ pub struct Todo {
     pub name: String,
     /// Status of the todo.
     pub done: bool,
-    /// Optional GitHub issue.
+    /// Optional remote issue. The field keeps its historic name for compatibility with
+    /// stored files, but may link to an issue on any supported forge.
     pub github: Option<GitHub>,
+    /// Optional pull request that implements the todo.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pull_request: Option<PullRequest>,
+    /// Optional commit the todo refers to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<Commit>,
+    /// Comments on the todo, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<Comment>,
+    /// Changes of the todo, oldest first. They are recorded automatically.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub activity: Vec<Activity>,
 }
 
 
@@ -23,44 +37,318 @@ impl Todo {
     /// Constructor. Creates a new Todo item.
     pub fn new(id: u32, name: &str, github: Option<GitHub>) -> Todo {
         Todo {
-            id: id,
+            id,
             name: name.to_string(),
             done: false,
-            github: github,
+            github,
+            pull_request: None,
+            commit: None,
+            comments: Vec::new(),
+            activity: Vec::new(),
         }
     }
 
     /// Edit the title of a given Todo.
     pub fn edit(&mut self, new_name: &str) {
+        if self.name != new_name {
+            self.record(ActivityKind::Renamed {
+                from: self.name.clone(),
+                to: new_name.to_string(),
+            });
+        }
         self.name = new_name.to_string();
     }
 
     /// Set the status of a Todo item to _done_.
     pub fn set_done(&mut self) {
+        if !self.done {
+            self.record(ActivityKind::Done);
+        }
         self.done = true;
     }
 
     /// Mark a todo item as _undone_.
     pub fn set_undone(&mut self) {
+        if self.done {
+            self.record(ActivityKind::Undone);
+        }
         self.done = false;
     }
+
+    /// Add a comment to the todo.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tdo_core::todo::*;
+    /// let mut todo = Todo::new(0, "Release 0.4", None);
+    /// todo.comment("Waiting for the changelog");
+    /// assert_eq!(todo.comments[0].body, "Waiting for the changelog");
+    /// ```
+    pub fn comment(&mut self, body: &str) {
+        self.comments.push(Comment::new(body));
+    }
+
+    /// Append a change to the activity log.
+    pub(crate) fn record(&mut self, kind: ActivityKind) {
+        self.activity.push(Activity {
+            at: SystemTime::now(),
+            kind,
+        });
+    }
 }
 
-/// Data Structure for a represented Github issue in an todo.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct GitHub {
+/// A comment on a todo.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Comment {
+    /// Text of the comment.
+    pub body: String,
+    /// Point in time when the comment was written.
+    pub created: SystemTime,
+    /// Login of the author, for comments mirrored from an issue.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// ID of the comment on the forge, for comments mirrored from or sent to an issue.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_id: Option<u64>,
+}
+
+impl Comment {
+    /// Constructor. Creates a new local comment written now.
+    pub fn new(body: &str) -> Comment {
+        Comment {
+            body: body.to_string(),
+            created: SystemTime::now(),
+            author: None,
+            remote_id: None,
+        }
+    }
+}
+
+/// An entry of the activity log of a todo.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Activity {
+    /// Point in time of the change.
+    pub at: SystemTime,
+    /// What changed.
+    pub kind: ActivityKind,
+}
+
+/// A change of a todo.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ActivityKind {
+    /// The todo was marked as done.
+    Done,
+    /// The todo was marked as undone.
+    Undone,
+    /// The todo was renamed.
+    Renamed {
+        /// Previous title.
+        from: String,
+        /// New title.
+        to: String,
+    },
+    /// The todo was moved to another list.
+    Moved {
+        /// Name of the previous list.
+        from: String,
+        /// Name of the new list.
+        to: String,
+    },
+}
+
+/// The forges tdo can link issues of.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Forge {
+    /// github.com or GitHub Enterprise.
+    #[default]
+    GitHub,
+    /// gitlab.com or a self-hosted GitLab.
+    GitLab,
+    /// A Gitea instance.
+    Gitea,
+}
+
+impl Forge {
+    /// Lowercase name of the forge, as used in stored files.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Forge::GitHub => "github",
+            Forge::GitLab => "gitlab",
+            Forge::Gitea => "gitea",
+        }
+    }
+
+    /// Look up a forge by its lowercase name.
+    pub fn from_name(name: &str) -> Option<Forge> {
+        match name {
+            "github" => Some(Forge::GitHub),
+            "gitlab" => Some(Forge::GitLab),
+            "gitea" => Some(Forge::Gitea),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn is_github(&self) -> bool {
+        *self == Forge::GitHub
+    }
+}
+
+/// Data Structure for an issue on a forge that is linked to a todo.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteIssue {
     /// Name of the repository (owner/repo).
     pub repo: String,
     /// Number of the issue.
     pub issue_number: u32,
+    /// Forge hosting the repository. It is only written to files for non-GitHub issues,
+    /// so GitHub links stay readable by earlier releases.
+    #[serde(default, skip_serializing_if = "Forge::is_github")]
+    pub forge: Forge,
+    /// Cache validators of the issue when it was last synchronized.
+    #[serde(default, skip_serializing_if = "CacheValidators::is_empty")]
+    pub cache: CacheValidators,
 }
 
-impl GitHub {
-    /// Constructor. Creates a new GitHub item.
-    pub fn new(repo: &str, issue_number: u32) -> GitHub {
-        GitHub {
+/// Issue links were GitHub-only before other forges were supported.
+pub type GitHub = RemoteIssue;
+
+impl RemoteIssue {
+    /// Constructor. Creates a new link to a GitHub issue.
+    pub fn new(repo: &str, issue_number: u32) -> RemoteIssue {
+        RemoteIssue::with_forge(Forge::GitHub, repo, issue_number)
+    }
+
+    /// Constructor. Creates a new link to an issue on the given forge.
+    pub fn with_forge(forge: Forge, repo: &str, issue_number: u32) -> RemoteIssue {
+        RemoteIssue {
+            repo: repo.to_owned(),
+            issue_number,
+            forge,
+            cache: CacheValidators::default(),
+        }
+    }
+
+    /// Whether both links point to the same issue, regardless of their cache validators.
+    pub fn same_issue(&self, other: &RemoteIssue) -> bool {
+        self.forge == other.forge && self.repo == other.repo && self.issue_number == other.issue_number
+    }
+}
+
+/// HTTP cache validators of a remote resource, used for conditional requests.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheValidators {
+    /// Value of the `ETag` header.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    /// Value of the `Last-Modified` header.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+}
+
+impl CacheValidators {
+    /// Whether no validator is known.
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+}
+
+/// State of a pull request (a merge request on GitLab).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PullState {
+    /// The pull request is open and ready for review.
+    #[default]
+    Open,
+    /// The pull request is open, but marked as a draft.
+    Draft,
+    /// The pull request was merged.
+    Merged,
+    /// The pull request was closed without being merged.
+    Closed,
+}
+
+impl PullState {
+    /// Lowercase name of the state, as used in stored files.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            PullState::Open => "open",
+            PullState::Draft => "draft",
+            PullState::Merged => "merged",
+            PullState::Closed => "closed",
+        }
+    }
+
+    /// Look up a state by its lowercase name.
+    pub fn from_name(name: &str) -> Option<PullState> {
+        match name {
+            "open" => Some(PullState::Open),
+            "draft" => Some(PullState::Draft),
+            "merged" => Some(PullState::Merged),
+            "closed" => Some(PullState::Closed),
+            _ => None,
+        }
+    }
+}
+
+/// Data Structure for a pull request that is linked to a todo.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PullRequest {
+    /// Name of the repository (owner/repo).
+    pub repo: String,
+    /// Number of the pull request.
+    pub number: u32,
+    /// State of the pull request when it was last synchronized.
+    #[serde(default)]
+    pub state: PullState,
+    /// Forge hosting the repository.
+    #[serde(default, skip_serializing_if = "Forge::is_github")]
+    pub forge: Forge,
+}
+
+impl PullRequest {
+    /// Constructor. Creates a new link to an open GitHub pull request.
+    pub fn new(repo: &str, number: u32) -> PullRequest {
+        PullRequest::with_forge(Forge::GitHub, repo, number)
+    }
+
+    /// Constructor. Creates a new link to an open pull request on the given forge.
+    pub fn with_forge(forge: Forge, repo: &str, number: u32) -> PullRequest {
+        PullRequest {
+            repo: repo.to_owned(),
+            number,
+            state: PullState::Open,
+            forge,
+        }
+    }
+}
+
+/// Data Structure for a commit that is linked to a todo.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Commit {
+    /// Name of the repository (owner/repo).
+    pub repo: String,
+    /// SHA of the commit, abbreviated or in full.
+    pub sha: String,
+    /// Forge hosting the repository.
+    #[serde(default, skip_serializing_if = "Forge::is_github")]
+    pub forge: Forge,
+}
+
+impl Commit {
+    /// Constructor. Creates a new link to a commit on GitHub.
+    pub fn new(repo: &str, sha: &str) -> Commit {
+        Commit::with_forge(Forge::GitHub, repo, sha)
+    }
+
+    /// Constructor. Creates a new link to a commit on the given forge.
+    pub fn with_forge(forge: Forge, repo: &str, sha: &str) -> Commit {
+        Commit {
             repo: repo.to_owned(),
-            issue_number: issue_number,
+            sha: sha.to_owned(),
+            forge,
         }
     }
 }
@@ -70,6 +358,8 @@ impl GitHub {
 pub struct GHIssueResponse {
     /// Repository URL.
     pub url: String,
+    /// URL of the issue in the browser.
+    pub html_url: String,
     /// Issue number.
     pub number: u32,
     /// Current state of the Issue.
@@ -0,0 +1,285 @@
+//! Applying GitHub webhook deliveries to a `Tdo` container.
+//!
+//! Services that receive GitHub webhooks pass the name of the event (the
+//! `X-GitHub-Event` header), the signature (the `X-Hub-Signature-256` header) and the raw
+//! payload to `handle`. The signature is checked against the shared secret first, then
+//! `issues` and `pull_request` events create, close, reopen or rename the todo linked to
+//! the issue or pull request. All other events and actions are ignored.
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use serde_json;
+use remote;
+use tdo::Tdo;
+use todo::{PullRequest, PullState, RemoteIssue, Todo};
+use error::*;
+
+/// What happened to an issue or pull request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// It was opened.
+    Opened,
+    /// Its title or, for pull requests, its draft state changed.
+    Edited,
+    /// It was closed or, for pull requests, merged.
+    Closed,
+    /// It was reopened.
+    Reopened,
+}
+
+/// The issue or pull request an event is about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Subject {
+    /// An issue.
+    Issue(RemoteIssue),
+    /// A pull request, with its state after the event.
+    PullRequest(PullRequest),
+}
+
+/// A webhook event that can be applied to a `Tdo` container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    /// What happened.
+    pub action: Action,
+    /// The issue or pull request it happened to.
+    pub subject: Subject,
+    /// Title of the issue or pull request after the event.
+    pub title: String,
+    /// Names of the labels of the issue or pull request after the event.
+    pub labels: Vec<String>,
+}
+
+/// Change of the container caused by an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    /// A todo with the given ID was created.
+    Created(u32),
+    /// The todo with the given ID was marked as done.
+    Closed(u32),
+    /// The todo with the given ID was marked as undone.
+    Reopened(u32),
+    /// The todo with the given ID was renamed.
+    Retitled(u32),
+    /// Only the stored state of the pull request of the todo with the given ID changed.
+    Updated(u32),
+    /// Nothing changed.
+    Unchanged,
+}
+
+/// Verify a delivery and apply it to the container.
+///
+/// New issues and pull requests are added to a list like in `apply`.
+/// This function returns a `GithubError::InvalidSignature` if the signature does not
+/// match and a `GithubError::InvalidPayload` if the payload can not be read.
+pub fn handle(tdo: &mut Tdo,
+              secret: &[u8],
+              event: &str,
+              signature: &str,
+              payload: &[u8],
+              list_name: Option<&str>)
+              -> TdoResult<Change> {
+    verify(secret, payload, signature)?;
+    match Event::parse(event, payload)? {
+        Some(event) => apply(tdo, &event, list_name),
+        None => Ok(Change::Unchanged),
+    }
+}
+
+/// Check the `sha256=<hex>` signature of a payload.
+///
+/// The comparison runs in constant time. This function returns a
+/// `GithubError::InvalidSignature` if the signature is malformed or does not match.
+///
+/// # Example
+///
+/// ```
+/// # use tdo_core::webhook;
+/// let signature = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+/// assert!(webhook::verify(b"It's a Secret to Everybody", b"Hello, World!", signature).is_ok());
+/// ```
+pub fn verify(secret: &[u8], payload: &[u8], signature: &str) -> TdoResult<()> {
+    let invalid = || Error::from(ErrorKind::GithubError(github_error::ErrorKind::InvalidSignature));
+    let expected = signature.strip_prefix("sha256=").and_then(decode_hex).ok_or_else(invalid)?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).map_err(|_| invalid())?;
+    mac.update(payload);
+    mac.verify_slice(&expected).map_err(|_| invalid())
+}
+
+impl Event {
+    /// Read the payload of an event.
+    ///
+    /// Returns `None` for events other than `issues` and `pull_request` and for actions
+    /// that do not affect todos, like labeling.
+    pub fn parse(event: &str, payload: &[u8]) -> TdoResult<Option<Event>> {
+        if event != "issues" && event != "pull_request" {
+            return Ok(None);
+        }
+        let payload: Payload = match serde_json::from_slice(payload) {
+            Ok(payload) => payload,
+            Err(e) => {
+                return Err(Error::with_chain(e,
+                                             ErrorKind::GithubError(github_error::ErrorKind::InvalidPayload(event.to_string()))))
+            }
+        };
+        let action = match payload.action.as_str() {
+            "opened" => Action::Opened,
+            "edited" | "ready_for_review" | "converted_to_draft" => Action::Edited,
+            "closed" => Action::Closed,
+            "reopened" => Action::Reopened,
+            _ => return Ok(None),
+        };
+        let repo = payload.repository.full_name;
+        let (subject, title, labels) = match (event, payload.issue, payload.pull_request) {
+            ("issues", Some(issue), _) => {
+                (Subject::Issue(RemoteIssue::new(&repo, issue.number)), issue.title, issue.labels)
+            }
+            ("pull_request", _, Some(pull)) => {
+                let mut link = PullRequest::new(&repo, pull.number);
+                link.state = remote::pull_state(pull.state == "closed", pull.merged, pull.draft);
+                (Subject::PullRequest(link), pull.title, pull.labels)
+            }
+            _ => return Err(ErrorKind::GithubError(github_error::ErrorKind::InvalidPayload(event.to_string())).into()),
+        };
+        Ok(Some(Event {
+            action,
+            subject,
+            title,
+            labels: labels.into_iter().map(|label| label.name).collect(),
+        }))
+    }
+}
+
+/// Apply an event to the todo linked to its issue or pull request.
+///
+/// Opened issues without a todo get one in the first list that tracks their repository
+/// and whose labels they carry, just like `remote::sync` imports them. Other opened
+/// issues and pull requests get a todo in the given list, or the default list.
+/// Pull requests only mark their todo as done when they are merged, like `remote::sync`
+/// does.
+pub fn apply(tdo: &mut Tdo, event: &Event, list_name: Option<&str>) -> TdoResult<Change> {
+    let todo = tdo.lists
+        .iter_mut()
+        .flat_map(|list| list.list.iter_mut())
+        .find(|todo| match event.subject {
+            Subject::Issue(ref link) => todo.github.as_ref().is_some_and(|github| github.same_issue(link)),
+            Subject::PullRequest(ref link) => {
+                todo.pull_request.as_ref().is_some_and(|pull| {
+                    pull.forge == link.forge && pull.repo == link.repo && pull.number == link.number
+                })
+            }
+        });
+    let todo = match todo {
+        Some(todo) => todo,
+        None if event.action == Action::Opened => {
+            let id = tdo.get_highest_id() + 1;
+            let mut todo = Todo::new(id, &event.title, None);
+            match event.subject {
+                Subject::Issue(ref link) => todo.github = Some(link.clone()),
+                Subject::PullRequest(ref link) => todo.pull_request = Some(link.clone()),
+            }
+            let index = match bound_list(tdo, event) {
+                Some(index) => index,
+                None => tdo.get_list_index(list_name.unwrap_or("default"))?,
+            };
+            tdo.lists[index].add(todo);
+            return Ok(Change::Created(id));
+        }
+        None => return Ok(Change::Unchanged),
+    };
+
+    let mut change = Change::Unchanged;
+    if let Subject::PullRequest(ref link) = event.subject {
+        if let Some(ref mut pull) = todo.pull_request {
+            if pull.state != link.state {
+                pull.state = link.state;
+                change = Change::Updated(todo.id);
+            }
+        }
+    }
+    let closed = match event.subject {
+        Subject::Issue(_) => true,
+        Subject::PullRequest(ref link) => link.state == PullState::Merged,
+    };
+    match event.action {
+        Action::Opened | Action::Edited if todo.name != event.title => {
+            todo.edit(&event.title);
+            change = Change::Retitled(todo.id);
+        }
+        Action::Closed if closed && !todo.done => {
+            todo.set_done();
+            change = Change::Closed(todo.id);
+        }
+        Action::Reopened if todo.done => {
+            todo.set_undone();
+            change = Change::Reopened(todo.id);
+        }
+        _ => {}
+    }
+    info!("applied webhook event to todo {}: {:?}", todo.id, change);
+    Ok(change)
+}
+
+// The first list that tracks the repository of an issue and whose labels all appear on
+// the issue.
+fn bound_list(tdo: &Tdo, event: &Event) -> Option<usize> {
+    let link = match event.subject {
+        Subject::Issue(ref link) => link,
+        Subject::PullRequest(_) => return None,
+    };
+    tdo.lists.iter().position(|list| {
+        list.forge == link.forge && list.repo.as_ref() == Some(&link.repo) &&
+        list.labels.iter().all(|label| {
+            let label = label.to_lowercase();
+            event.labels.iter().any(|other| other.to_lowercase() == label)
+        })
+    })
+}
+
+#[derive(Deserialize)]
+struct Payload {
+    action: String,
+    repository: Repository,
+    issue: Option<IssuePayload>,
+    pull_request: Option<PullPayload>,
+}
+
+#[derive(Deserialize)]
+struct Repository {
+    full_name: String,
+}
+
+#[derive(Deserialize)]
+struct IssuePayload {
+    number: u32,
+    title: String,
+    #[serde(default)]
+    labels: Vec<LabelPayload>,
+}
+
+#[derive(Deserialize)]
+struct PullPayload {
+    number: u32,
+    title: String,
+    state: String,
+    #[serde(default)]
+    merged: bool,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    labels: Vec<LabelPayload>,
+}
+
+#[derive(Deserialize)]
+struct LabelPayload {
+    name: String,
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    // `from_str_radix` would accept a leading `+` as well
+    if !hex.len().is_multiple_of(2) || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| hex.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+        .collect()
+}
@@ -0,0 +1,80 @@
+extern crate tdo_core;
+extern crate serde_json;
+
+
+#[cfg(test)]
+mod activity {
+    use tdo_core::{tdo, list, todo};
+    use tdo_core::todo::ActivityKind;
+
+    fn kinds(todo: &todo::Todo) -> Vec<ActivityKind> {
+        todo.activity.iter().map(|activity| activity.kind.clone()).collect()
+    }
+
+    #[test]
+    fn state_changes_and_renames() {
+        let mut todo = todo::Todo::new(0, "Write docs", None);
+        assert!(todo.activity.is_empty());
+
+        todo.set_done();
+        todo.set_done();
+        todo.set_undone();
+        todo.edit("Write the docs");
+        todo.edit("Write the docs");
+        assert_eq!(kinds(&todo),
+                   vec![ActivityKind::Done,
+                        ActivityKind::Undone,
+                        ActivityKind::Renamed {
+                            from: "Write docs".to_string(),
+                            to: "Write the docs".to_string(),
+                        }]);
+        assert!(todo.activity.windows(2).all(|pair| pair[0].at <= pair[1].at));
+    }
+
+    #[test]
+    fn moves() {
+        let mut tdo = tdo::Tdo::new();
+        tdo.add_list(list::TodoList::new("work")).unwrap();
+        tdo.add_todo(None, todo::Todo::new(0, "Move me", None)).unwrap();
+        tdo.move_todo(0, "work").unwrap();
+        tdo.move_todo(0, "work").unwrap();
+        tdo.done_id(0).unwrap();
+
+        assert_eq!(kinds(&tdo.lists[1].list[0]),
+                   vec![ActivityKind::Moved {
+                            from: "default".to_string(),
+                            to: "work".to_string(),
+                        },
+                        ActivityKind::Done]);
+    }
+
+    #[test]
+    fn comments() {
+        let mut todo = todo::Todo::new(0, "Release 0.4", None);
+        todo.comment("Waiting for the changelog");
+        todo.comment("Changelog is done");
+
+        let bodies: Vec<&str> = todo.comments.iter().map(|comment| comment.body.as_str()).collect();
+        assert_eq!(bodies, vec!["Waiting for the changelog", "Changelog is done"]);
+        assert!(todo.comments[0].created <= todo.comments[1].created);
+        assert_eq!(todo.comments[0].author, None);
+        assert!(todo.activity.is_empty());
+    }
+
+    #[test]
+    fn serde() {
+        let plain = serde_json::to_value(todo::Todo::new(0, "Plain", None)).unwrap();
+        assert!(plain.get("comments").is_none());
+        assert!(plain.get("activity").is_none());
+
+        let mut todo = todo::Todo::new(0, "Commented", None);
+        todo.comment("A comment");
+        todo.edit("Renamed");
+        let json = serde_json::to_string(&todo).unwrap();
+        assert!(json.contains(r#""type":"renamed""#));
+
+        let loaded: todo::Todo = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.comments, todo.comments);
+        assert_eq!(loaded.activity, todo.activity);
+    }
+}
@@ -0,0 +1,182 @@
+//! A minimal HTTP server and client for testing the remote issue clients offline.
+#![allow(dead_code)]
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tdo_core::error::*;
+use tdo_core::http::{HttpClient, Request, Response};
+
+/// A request received by the mock server.
+#[derive(Debug, Clone)]
+pub struct Received {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Received {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|header| header.0.eq_ignore_ascii_case(name))
+            .map(|header| header.1.as_str())
+    }
+}
+
+/// The answer of the mock server to a request.
+#[derive(Debug, Clone)]
+pub struct Reply {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Reply {
+    pub fn json(status: u16, body: &str) -> Reply {
+        Reply {
+            status,
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body: body.to_string(),
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Reply {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// Serves every connection on a local port with the given handler.
+pub struct MockServer {
+    url: String,
+    received: Arc<Mutex<Vec<Received>>>,
+}
+
+impl MockServer {
+    pub fn start<F>(handler: F) -> MockServer
+        where F: Fn(&Received) -> Reply + Send + 'static
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let received = Arc::new(Mutex::new(vec![]));
+        let log = received.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => continue,
+                };
+                let request = match read_request(&mut BufReader::new(&stream)) {
+                    Some(request) => request,
+                    None => continue,
+                };
+                let reply = handler(&request);
+                log.lock().unwrap().push(request);
+                let mut head = format!("HTTP/1.1 {} Mock\r\nContent-Length: {}\r\nConnection: close\r\n",
+                                       reply.status,
+                                       reply.body.len());
+                for (name, value) in reply.headers {
+                    head.push_str(&format!("{}: {}\r\n", name, value));
+                }
+                head.push_str("\r\n");
+                let _ = stream.write_all(head.as_bytes());
+                let _ = stream.write_all(reply.body.as_bytes());
+            }
+        });
+        MockServer { url, received }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn received(&self) -> Vec<Received> {
+        self.received.lock().unwrap().clone()
+    }
+}
+
+/// Sends plain HTTP/1.1 requests, so the tests do not depend on the `http` feature.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TestClient;
+
+impl HttpClient for TestClient {
+    fn send(&mut self, request: &Request) -> TdoResult<Response> {
+        let failed = || {
+            Error::from(ErrorKind::GithubError(github_error::ErrorKind::RequestFailed(request.url.clone())))
+        };
+        let address = request.url.strip_prefix("http://").ok_or_else(failed)?;
+        let (host, path) = match address.find('/') {
+            Some(index) => address.split_at(index),
+            None => (address, "/"),
+        };
+        let body = request.body.clone().unwrap_or_default();
+        let mut head = format!("{} {} HTTP/1.1\r\nHost: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+                               request.method.as_str(),
+                               path,
+                               host,
+                               body.len());
+        for (name, value) in &request.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str("\r\n");
+
+        let mut stream = TcpStream::connect(host).map_err(|_| failed())?;
+        stream.write_all(head.as_bytes()).and_then(|_| stream.write_all(body.as_bytes())).map_err(|_| failed())?;
+        read_response(&mut BufReader::new(stream)).ok_or_else(failed)
+    }
+}
+
+fn read_response<R: BufRead>(reader: &mut R) -> Option<Response> {
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let status = line.split_whitespace().nth(1)?.parse().ok()?;
+    let headers = read_headers(reader)?;
+    let mut body = String::new();
+    reader.read_to_string(&mut body).ok()?;
+    Some(Response {
+        status,
+        headers,
+        body,
+    })
+}
+
+fn read_headers<R: BufRead>(reader: &mut R) -> Option<Vec<(String, String)>> {
+    let mut headers = vec![];
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).ok()?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            return Some(headers);
+        }
+        let mut split = line.splitn(2, ':');
+        let name = split.next()?.trim().to_string();
+        let value = split.next().unwrap_or("").trim().to_string();
+        headers.push((name, value));
+    }
+}
+
+fn read_request<R: BufRead>(reader: &mut R) -> Option<Received> {
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let path = parts.next()?.to_string();
+
+    let headers = read_headers(reader)?;
+
+    let length = headers.iter()
+        .find(|header| header.0.eq_ignore_ascii_case("content-length"))
+        .and_then(|header| header.1.parse::<usize>().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).ok()?;
+    Some(Received {
+        method,
+        path,
+        headers,
+        body: String::from_utf8(body).ok()?,
+    })
+}
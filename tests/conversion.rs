@@ -0,0 +1,52 @@
+extern crate tdo_core;
+
+
+#[cfg(test)]
+mod conversion {
+    use std::fs;
+    use tdo_core::{storage, tdo};
+    use tdo_core::migration::SkipReason;
+
+    #[test]
+    fn report_legacy() {
+        let report = storage::dry_run("tests/versions/version00.json").unwrap();
+        assert_eq!(report.source_version, "0.0.0");
+        assert_eq!(report.steps.len(), 3);
+        assert_eq!(report.lists, 2);
+        assert_eq!(report.todos, 3);
+        assert!(report.skipped.is_empty());
+    }
+
+    #[test]
+    fn report_skipped_entries() {
+        let path = "tests/versions/version00_broken.json";
+        let before = fs::read_to_string(path).unwrap();
+        let report = storage::dry_run(path).unwrap();
+
+        assert_eq!(report.lists, 2);
+        assert_eq!(report.todos, 2);
+        let skipped: Vec<_> = report.skipped
+            .iter()
+            .map(|entry| (entry.list.as_str(), entry.entry.as_deref(), entry.reason))
+            .collect();
+        assert_eq!(skipped,
+                   vec![("default", Some("two"), SkipReason::InvalidId),
+                        ("default", Some("3"), SkipReason::MissingState),
+                        ("default", Some("4"), SkipReason::InvalidEntry),
+                        ("work", None, SkipReason::InvalidList)]);
+
+        // a dry run must never touch the file
+        assert_eq!(fs::read_to_string(path).unwrap(), before);
+        // loading still refuses to drop data silently
+        assert!(tdo::Tdo::load(path).is_err());
+    }
+
+    #[test]
+    fn report_current() {
+        let report = storage::dry_run("tests/versions/version01.json").unwrap();
+        assert_eq!(report.source_version, "0.1.1");
+        assert_eq!(report.steps.len(), 2);
+        assert_eq!(report.lists, 1);
+        assert_eq!(report.todos, 0);
+    }
+}
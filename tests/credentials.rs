@@ -0,0 +1,99 @@
+extern crate tdo_core;
+
+
+#[cfg(test)]
+mod credentials {
+    use std::env;
+    use std::fs;
+    use tdo_core::tdo;
+    use tdo_core::credentials::*;
+
+    fn temp_path(name: &str) -> String {
+        env::temp_dir().join(name).to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn file_store() {
+        let path = temp_path("tdo_credentials_store.json");
+        let _ = fs::remove_file(&path);
+        let mut store = FileCredentials::new(&path);
+        assert_eq!(store.token(GITHUB).unwrap(), None);
+
+        store.set_token(GITHUB, "first").unwrap();
+        store.set_token("gitlab", "second").unwrap();
+        store.set_token(GITHUB, "third").unwrap();
+
+        let store = FileCredentials::new(&path);
+        assert_eq!(store.token(GITHUB).unwrap(), Some("third".to_string()));
+        assert_eq!(store.token("gitlab").unwrap(), Some("second".to_string()));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn restrict_existing_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = temp_path("tdo_credentials_mode.json");
+        fs::write(&path, "{}").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        FileCredentials::new(&path).set_token(GITHUB, "secret").unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+
+    #[test]
+    fn env_store() {
+        let mut store = EnvCredentials::with_prefix("TDO_TEST");
+        assert_eq!(store.variable("git.example.com"), "TDO_TEST_GIT_EXAMPLE_COM_TOKEN");
+        assert_eq!(store.token(GITHUB).unwrap(), None);
+
+        env::set_var("TDO_TEST_GITHUB_TOKEN", "from-env");
+        assert_eq!(store.token(GITHUB).unwrap(), Some("from-env".to_string()));
+        assert!(store.set_token(GITHUB, "other").is_err());
+    }
+
+    #[test]
+    fn keep_token_on_load() {
+        let path = temp_path("tdo_credentials_load_save.json");
+        let creds = temp_path("tdo_credentials_load_save_store.json");
+        let _ = fs::remove_file(&creds);
+        fs::copy("tests/versions/version02.json", &path).unwrap();
+
+        let tdo = tdo::Tdo::load(&path).unwrap();
+        assert_eq!(tdo.lists[0].list[0].github.as_ref().unwrap().issue_number, 7);
+        tdo.save(&path).unwrap();
+        assert!(!fs::read_to_string(&path).unwrap().contains("LeakedToken"));
+
+        let token = tdo.get_gh_token().unwrap();
+        assert_eq!(token, "LeakedToken");
+        let mut store = FileCredentials::new(&creds);
+        store.set_token(GITHUB, &token).unwrap();
+        assert_eq!(store.token(GITHUB).unwrap(), Some("LeakedToken".to_string()));
+    }
+
+    #[test]
+    fn never_save_token() {
+        let path = temp_path("tdo_credentials_save.json");
+        let mut tdo = tdo::Tdo::new();
+        tdo.set_gh_token("SessionToken");
+        tdo.save(&path).unwrap();
+
+        assert!(!fs::read_to_string(&path).unwrap().contains("SessionToken"));
+    }
+
+    #[test]
+    fn migrate_old_token() {
+        let data = temp_path("tdo_credentials_migrate.json");
+        let creds = temp_path("tdo_credentials_migrate_store.json");
+        let _ = fs::remove_file(&creds);
+        fs::copy("tests/versions/version02.json", &data).unwrap();
+        let mut store = FileCredentials::new(&creds);
+
+        assert!(migrate_token(&data, &mut store).unwrap());
+        assert_eq!(store.token(GITHUB).unwrap(), Some("LeakedToken".to_string()));
+        assert!(!fs::read_to_string(&data).unwrap().contains("LeakedToken"));
+
+        // nothing left to migrate
+        assert!(!migrate_token(&data, &mut store).unwrap());
+    }
+}
@@ -100,4 +100,14 @@ mod interact {
         assert!(tdo.lists[1].list[0].done);
     }
 
+    #[test]
+    fn set_undone_tdo() {
+        let mut tdo = tdo::Tdo::new();
+        tdo.add_todo(None, todo::Todo::new(0, "First Entry", None)).unwrap();
+
+        tdo.done_id(0).unwrap();
+        tdo.undone_id(0).unwrap();
+        assert!(!tdo.lists[0].list[0].done);
+    }
+
 }
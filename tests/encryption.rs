@@ -0,0 +1,102 @@
+#![cfg(feature = "encryption")]
+extern crate tdo_core;
+
+
+#[cfg(test)]
+mod encryption {
+    use std::env;
+    use std::fs;
+    use tdo_core::{tdo, list, todo};
+    use tdo_core::encryption::EncryptedFile;
+    use tdo_core::error::*;
+
+    fn sample() -> tdo::Tdo {
+        let mut tdo = tdo::Tdo::new();
+        tdo.add_list(list::TodoList::new("clients")).unwrap();
+        tdo.add_todo(Some("clients"), todo::Todo::new(0, "Call ACME Corp", None)).unwrap();
+        tdo.set_gh_token("SecretToken");
+        tdo
+    }
+
+    fn temp_path(name: &str) -> String {
+        env::temp_dir().join(name).to_str().unwrap().to_string()
+    }
+
+    fn is_wrong_passphrase(res: TdoResult<tdo::Tdo>) -> bool {
+        matches!(res,
+                 Err(Error(ErrorKind::StorageError(storage_error::ErrorKind::WrongPassphrase(_)), _)))
+    }
+
+    #[test]
+    fn roundtrip() {
+        let path = temp_path("tdo_encrypted_roundtrip.tdo");
+        sample().save_encrypted(&path, "passphrase").unwrap();
+
+        let raw = fs::read(&path).unwrap();
+        let raw = String::from_utf8_lossy(&raw);
+        assert!(!raw.contains("ACME"));
+        assert!(!raw.contains("SecretToken"));
+
+        let loaded = tdo::Tdo::load_encrypted(&path, "passphrase").unwrap();
+        assert_eq!(loaded.lists[1].list[0].name, "Call ACME Corp");
+    }
+
+    #[test]
+    fn wrong_passphrase() {
+        let path = temp_path("tdo_encrypted_wrong.tdo");
+        sample().save_encrypted(&path, "passphrase").unwrap();
+
+        assert!(is_wrong_passphrase(tdo::Tdo::load_encrypted(&path, "guess")));
+    }
+
+    #[test]
+    fn plain_file() {
+        let path = temp_path("tdo_encrypted_plain.json");
+        sample().save(&path).unwrap();
+
+        match tdo::Tdo::load_encrypted(&path, "passphrase") {
+            Err(Error(ErrorKind::StorageError(storage_error::ErrorKind::NotEncrypted(_)), _)) => {}
+            other => panic!("expected NotEncrypted, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rekey() {
+        let path = temp_path("tdo_encrypted_rekey.tdo");
+        sample().save_encrypted(&path, "old").unwrap();
+
+        assert!(EncryptedFile::new(&path, "wrong").rekey("new").is_err());
+        EncryptedFile::new(&path, "old").rekey("new").unwrap();
+
+        assert!(is_wrong_passphrase(tdo::Tdo::load_encrypted(&path, "old")));
+        let loaded = tdo::Tdo::load_encrypted(&path, "new").unwrap();
+        assert_eq!(loaded.lists[1].list[0].name, "Call ACME Corp");
+    }
+
+    #[test]
+    fn failed_save_keeps_file() {
+        let path = temp_path("tdo_encrypted_failed_save.tdo");
+        let tmp = format!("{}.tmp", path);
+        let _ = fs::remove_dir(&tmp);
+        sample().save_encrypted(&path, "old").unwrap();
+        assert!(fs::metadata(&tmp).is_err());
+
+        // A directory in place of the temporary file makes every write fail.
+        fs::create_dir(&tmp).unwrap();
+        let mut storage = EncryptedFile::new(&path, "old");
+        assert!(storage.rekey("new").is_err());
+        assert!(tdo::Tdo::new().save_encrypted(&path, "old").is_err());
+        fs::remove_dir(&tmp).unwrap();
+
+        let loaded = tdo::Tdo::load_encrypted(&path, "old").unwrap();
+        assert_eq!(loaded.lists[1].list[0].name, "Call ACME Corp");
+        storage.rekey("new").unwrap();
+        assert!(tdo::Tdo::load_encrypted(&path, "new").is_ok());
+    }
+
+    #[test]
+    fn hidden_passphrase() {
+        let storage = EncryptedFile::new("foo.tdo", "hunter2");
+        assert!(!format!("{:?}", storage).contains("hunter2"));
+    }
+}
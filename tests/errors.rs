@@ -0,0 +1,186 @@
+extern crate tdo_core;
+extern crate serde_json;
+
+
+#[cfg(test)]
+mod context {
+    use std::env;
+    use std::error::Error as StdError;
+    use std::fs;
+    use std::io;
+    use tdo_core::{tdo, list, todo};
+    use tdo_core::error::*;
+
+    #[test]
+    fn missing_todo() {
+        let mut tdo = tdo::Tdo::new();
+        let err = tdo.done_id(42).unwrap_err();
+        match *err.kind() {
+            ErrorKind::TodoError(todo_error::ErrorKind::NotInList(id)) => assert_eq!(id, 42),
+            ref other => panic!("expected NotInList, got {:?}", other),
+        }
+        assert!(err.to_string().contains("42"));
+    }
+
+    #[test]
+    fn missing_list() {
+        let mut tdo = tdo::Tdo::new();
+        let err = tdo.add_todo(Some("work"), todo::Todo::new(0, "Entry", None)).unwrap_err();
+        match *err.kind() {
+            ErrorKind::TodoError(todo_error::ErrorKind::NoSuchList(ref name)) => {
+                assert_eq!(name, "work")
+            }
+            ref other => panic!("expected NoSuchList, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn duplicate_list() {
+        let mut tdo = tdo::Tdo::new();
+        tdo.add_list(list::TodoList::new("work")).unwrap();
+        let err = tdo.add_list(list::TodoList::new("Work")).unwrap_err();
+        assert_eq!(err.to_string(), "There already exists a list named 'Work'");
+    }
+
+    #[test]
+    fn moving_issue() {
+        let mut tdo = tdo::Tdo::new();
+        tdo.add_list(list::TodoList::new("work")).unwrap();
+        tdo.add_todo(None,
+                      todo::Todo::new(3, "Issue", Some(todo::GitHub::new("tdolist/tdo", 1))))
+            .unwrap();
+        let err = tdo.move_todo(3, "work").unwrap_err();
+        match *err.kind() {
+            ErrorKind::GithubError(github_error::ErrorKind::NotAllowedToMove(id)) => assert_eq!(id, 3),
+            ref other => panic!("expected NotAllowedToMove, got {:?}", other),
+        }
+        assert_eq!(err.to_string(),
+                   "Todo 3 is linked to an issue and cannot be moved to this list under the current move policy");
+    }
+
+    #[test]
+    fn missing_file_keeps_io_error() {
+        let err = tdo::Tdo::load("tests/versions/missing.json").unwrap_err();
+        match *err.kind() {
+            ErrorKind::StorageError(storage_error::ErrorKind::FileNotFound(ref path)) => {
+                assert_eq!(path, "tests/versions/missing.json")
+            }
+            ref other => panic!("expected FileNotFound, got {:?}", other),
+        }
+        let cause = err.source().expect("the io::Error is kept as cause");
+        let io_error = cause.downcast_ref::<io::Error>().unwrap();
+        assert_eq!(io_error.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn syntax_error_position() {
+        let path = env::temp_dir().join("tdo_errors_syntax.json");
+        fs::write(&path, "{\n  \"lists\": [\n    oops\n  ]\n}").unwrap();
+        let path = path.to_str().unwrap();
+
+        match *tdo::Tdo::load(path).unwrap_err().kind() {
+            ErrorKind::StorageError(storage_error::ErrorKind::ParseError(ref file, line, column)) => {
+                assert_eq!(file, path);
+                assert_eq!(line, 3);
+                assert_eq!(column, 5);
+            }
+            ref other => panic!("expected ParseError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn save_failure_path() {
+        let err = tdo::Tdo::new().save("tests/no/such/dir/tdo.json").unwrap_err();
+        match *err.kind() {
+            ErrorKind::StorageError(storage_error::ErrorKind::SaveFailure(ref path)) => {
+                assert_eq!(path, "tests/no/such/dir/tdo.json")
+            }
+            ref other => panic!("expected SaveFailure, got {:?}", other),
+        }
+        assert!(err.source().is_some());
+    }
+}
+
+
+#[cfg(test)]
+mod codes {
+    use std::collections::HashSet;
+    use serde_json;
+    use tdo_core::tdo;
+    use tdo_core::error::*;
+
+    fn all_kinds() -> Vec<ErrorKind> {
+        vec![ErrorKind::Msg("plain".to_string()),
+             ErrorKind::TodoError(todo_error::ErrorKind::Msg("plain".to_string())),
+             ErrorKind::TodoError(todo_error::ErrorKind::NotInList(1)),
+             ErrorKind::TodoError(todo_error::ErrorKind::NoSuchList("a".to_string())),
+             ErrorKind::TodoError(todo_error::ErrorKind::CanNotRemoveDefault),
+             ErrorKind::TodoError(todo_error::ErrorKind::NameAlreadyExists("a".to_string())),
+             ErrorKind::TodoError(todo_error::ErrorKind::IDAlreadyExists(1)),
+             ErrorKind::TodoError(todo_error::ErrorKind::VirtualList("a".to_string())),
+             ErrorKind::StorageError(storage_error::ErrorKind::Msg("plain".to_string())),
+             ErrorKind::StorageError(storage_error::ErrorKind::FileCorrupted("a".to_string())),
+             ErrorKind::StorageError(storage_error::ErrorKind::ParseError("a".to_string(), 1, 1)),
+             ErrorKind::StorageError(storage_error::ErrorKind::MalformedData("a".to_string())),
+             ErrorKind::StorageError(storage_error::ErrorKind::SaveFailure("a".to_string())),
+             ErrorKind::StorageError(storage_error::ErrorKind::FileNotFound("a".to_string())),
+             ErrorKind::StorageError(storage_error::ErrorKind::UnableToConvert("a".to_string())),
+             ErrorKind::StorageError(storage_error::ErrorKind::UnsupportedVersion("a".to_string())),
+             ErrorKind::StorageError(storage_error::ErrorKind::UnsupportedFormat("a".to_string())),
+             ErrorKind::StorageError(storage_error::ErrorKind::WrongPassphrase("a".to_string())),
+             ErrorKind::StorageError(storage_error::ErrorKind::NotEncrypted("a".to_string())),
+             ErrorKind::StorageError(storage_error::ErrorKind::ReadOnlyCredentials("a".to_string())),
+             ErrorKind::GithubError(github_error::ErrorKind::Msg("plain".to_string())),
+             ErrorKind::GithubError(github_error::ErrorKind::DoesNotExist("a".to_string())),
+             ErrorKind::GithubError(github_error::ErrorKind::BadCredentials),
+             ErrorKind::GithubError(github_error::ErrorKind::NotAllowedToMove(1)),
+             ErrorKind::GithubError(github_error::ErrorKind::NoIssueAsigned(1)),
+             ErrorKind::GithubError(github_error::ErrorKind::MissingToken),
+             ErrorKind::GithubError(github_error::ErrorKind::UnknownError(500)),
+             ErrorKind::GithubError(github_error::ErrorKind::RequestFailed("a".to_string())),
+             ErrorKind::GithubError(github_error::ErrorKind::RateLimited(0)),
+             ErrorKind::GithubError(github_error::ErrorKind::AlreadyLinked(1)),
+             ErrorKind::GithubError(github_error::ErrorKind::InvalidSignature),
+             ErrorKind::GithubError(github_error::ErrorKind::InvalidPayload("issues".to_string())),
+             ErrorKind::GithubError(github_error::ErrorKind::NoRepository("a".to_string())),
+             ErrorKind::GithubError(github_error::ErrorKind::InvalidResponse("a".to_string())),
+             ErrorKind::QueryError(query_error::ErrorKind::Msg("plain".to_string())),
+             ErrorKind::QueryError(query_error::ErrorKind::UnknownField("a".to_string(), 0)),
+             ErrorKind::QueryError(query_error::ErrorKind::InvalidOperator("<".to_string(), 0)),
+             ErrorKind::QueryError(query_error::ErrorKind::InvalidValue("a".to_string(), 0)),
+             ErrorKind::QueryError(query_error::ErrorKind::MissingValue(0)),
+             ErrorKind::QueryError(query_error::ErrorKind::UnterminatedQuote(0))]
+    }
+
+    #[test]
+    fn codes_are_unique() {
+        let kinds = all_kinds();
+        let codes: HashSet<_> = kinds.iter().map(|kind| kind.code()).collect();
+        let numeric: HashSet<_> = kinds.iter().map(|kind| kind.numeric_code()).collect();
+        assert_eq!(codes.len(), kinds.len());
+        assert_eq!(numeric.len(), kinds.len());
+    }
+
+    #[test]
+    fn stable_codes() {
+        let kind = ErrorKind::StorageError(storage_error::ErrorKind::FileNotFound("a".to_string()));
+        assert_eq!(kind.code(), "storage.file_not_found");
+        assert_eq!(kind.numeric_code(), 205);
+        let kind = ErrorKind::GithubError(github_error::ErrorKind::MissingToken);
+        assert_eq!(kind.code(), "github.missing_token");
+        assert_eq!(kind.numeric_code(), 305);
+    }
+
+    #[test]
+    fn json_report() {
+        let err = tdo::Tdo::load("tests/versions/missing.json").unwrap_err();
+        let json = serde_json::to_value(err.report()).unwrap();
+        assert_eq!(json["code"], "storage.file_not_found");
+        assert_eq!(json["numeric_code"], 205);
+        assert_eq!(json["message"], "File 'tests/versions/missing.json' was not found");
+        assert_eq!(json["causes"].as_array().unwrap().len(), 1);
+
+        let report: ErrorReport = serde_json::from_value(json).unwrap();
+        assert_eq!(report, err.report());
+    }
+}
@@ -0,0 +1,295 @@
+extern crate tdo_core;
+#[macro_use]
+extern crate serde_json;
+
+mod common;
+
+
+#[cfg(test)]
+mod links {
+    use serde_json;
+    use tdo_core::todo::{Commit, Forge, GitHub, PullRequest, PullState, RemoteIssue, Todo};
+
+    #[test]
+    fn github_links_keep_their_format() {
+        let todo = Todo::new(0, "Issue", Some(GitHub::new("tdolist/tdo", 1)));
+        let json = serde_json::to_value(&todo).unwrap();
+        assert_eq!(json["github"], json!({ "repo": "tdolist/tdo", "issue_number": 1 }));
+        assert!(json.get("pull_request").is_none() && json.get("commit").is_none());
+    }
+
+    #[test]
+    fn pull_requests_and_commits() {
+        let mut todo = Todo::new(0, "Review", None);
+        todo.pull_request = Some(PullRequest::with_forge(Forge::GitLab, "group/project", 3));
+        todo.commit = Some(Commit::new("tdolist/tdo", "1f2e3d4"));
+        let json = serde_json::to_value(&todo).unwrap();
+        assert_eq!(json["pull_request"],
+                   json!({ "repo": "group/project", "number": 3, "state": "open", "forge": "gitlab" }));
+        assert_eq!(json["commit"], json!({ "repo": "tdolist/tdo", "sha": "1f2e3d4" }));
+
+        let loaded: Todo = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded.pull_request.unwrap().state, PullState::Open);
+        assert_eq!(loaded.commit.unwrap().sha, "1f2e3d4");
+    }
+
+    #[test]
+    fn other_forges_roundtrip() {
+        let todo = Todo::new(0, "Issue", Some(RemoteIssue::with_forge(Forge::Gitea, "me/tdo", 2)));
+        let json = serde_json::to_string(&todo).unwrap();
+        assert!(json.contains("\"forge\":\"gitea\""));
+        let loaded: Todo = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.github.unwrap().forge, Forge::Gitea);
+    }
+}
+
+
+#[cfg(test)]
+mod gitlab {
+    use serde_json::{self, Value};
+    use common::{MockServer, Received, Reply, TestClient};
+    use tdo_core::{tdo, list, todo};
+    use tdo_core::error::*;
+    use tdo_core::gitlab::GitLabClient;
+    use tdo_core::remote::{self, IssueFilter};
+    use tdo_core::todo::{Forge, RemoteIssue};
+
+    fn issue(iid: u32, state: &str, title: &str) -> String {
+        format!(r#"{{"id": 900{0}, "iid": {0}, "state": "{1}", "title": "{2}",
+                    "web_url": "https://gitlab.com/group/project/-/issues/{0}"}}"#,
+                iid,
+                state,
+                title)
+    }
+
+    fn stub(request: &Received) -> Reply {
+        if request.header("PRIVATE-TOKEN") != Some("secret") {
+            return Reply::json(401, r#"{"message": "401 Unauthorized"}"#);
+        }
+        let body: Value = serde_json::from_str(&request.body).unwrap_or(Value::Null);
+        match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/api/v4/projects/group%2Fproject/issues/1") => Reply::json(200, &issue(1, "closed", "Done upstream")),
+            ("GET", "/api/v4/projects/group%2Fproject/merge_requests/8") => {
+                Reply::json(200,
+                            r#"{"iid": 8, "title": "Fix", "state": "merged", "draft": false,
+                                "web_url": "https://gitlab.com/group/project/-/merge_requests/8"}"#)
+            }
+            ("GET", path) if path.starts_with("/api/v4/projects/group%2Fproject/issues?") => {
+                if path.contains("page=2") {
+                    Reply::json(200, &format!("[{}]", issue(3, "opened", "Third")))
+                } else {
+                    let next = format!("<http://{}/api/v4/projects/group%2Fproject/issues?page=2>; rel=\"next\"",
+                                       request.header("Host").unwrap());
+                    Reply::json(200, &format!("[{}]", issue(2, "opened", "Second"))).header("Link", &next)
+                }
+            }
+            ("POST", "/api/v4/projects/group%2Fproject/issues") => {
+                Reply::json(201, &issue(4, "opened", body["title"].as_str().unwrap()))
+            }
+            ("PUT", "/api/v4/projects/group%2Fproject/issues/1") => {
+                let state = if body["state_event"] == "close" { "closed" } else { "opened" };
+                Reply::json(200, &issue(1, state, "Done upstream"))
+            }
+            _ => Reply::json(404, r#"{"message": "404 Project Not Found"}"#),
+        }
+    }
+
+    fn container() -> tdo::Tdo {
+        let mut tdo = tdo::Tdo::new();
+        tdo.add_list(list::TodoList::new("gitlab")).unwrap();
+        tdo.add_todo(Some("gitlab"),
+                      todo::Todo::new(0, "Linked", Some(RemoteIssue::with_forge(Forge::GitLab, "group/project", 1))))
+            .unwrap();
+        tdo.add_todo(None,
+                      todo::Todo::new(1, "On GitHub", Some(todo::GitHub::new("group/project", 1))))
+            .unwrap();
+        tdo.add_todo(None, todo::Todo::new(2, "Local", None)).unwrap();
+        tdo
+    }
+
+    fn client(server: &MockServer, token: &str) -> GitLabClient<TestClient> {
+        GitLabClient::with_api_url(TestClient, token, &format!("{}/api/v4", server.url()))
+    }
+
+    #[test]
+    fn sync_only_gitlab_links() {
+        let server = MockServer::start(stub);
+        let mut tdo = container();
+        let report = remote::sync(&mut tdo, &mut client(&server, "secret")).unwrap();
+        assert_eq!(report.checked, 1);
+        assert!(tdo.lists[1].list[0].done);
+        assert_eq!(tdo.lists[1].list[0].name, "Done upstream");
+        assert_eq!(tdo.lists[0].list[0].name, "On GitHub");
+    }
+
+    #[test]
+    fn merged_merge_request() {
+        let server = MockServer::start(stub);
+        let mut tdo = container();
+        tdo.lists[0].list[1].pull_request = Some(todo::PullRequest::with_forge(Forge::GitLab, "group/project", 8));
+        let report = remote::sync(&mut tdo, &mut client(&server, "secret")).unwrap();
+        assert_eq!(report.merged, vec![2]);
+        assert!(tdo.lists[0].list[1].done);
+    }
+
+    #[test]
+    fn import_all_pages() {
+        let server = MockServer::start(stub);
+        let mut tdo = container();
+        let filter = IssueFilter::new().label("bug").assignee("me");
+        let report = remote::import(&mut tdo, &mut client(&server, "secret"), "group/project", "gitlab", &filter)
+            .unwrap();
+        assert_eq!(report.added.len(), 2);
+        let link = tdo.lists[1].list[2].github.clone().unwrap();
+        assert_eq!(link, RemoteIssue::with_forge(Forge::GitLab, "group/project", 3));
+
+        let path = &server.received()[0].path;
+        assert!(path.contains("state=opened"));
+        assert!(path.contains("labels=bug"));
+        assert!(path.contains("assignee_username=me"));
+    }
+
+    #[test]
+    fn promote_and_close() {
+        let server = MockServer::start(stub);
+        let mut tdo = container();
+        let mut client = client(&server, "secret");
+
+        let link = remote::promote(&mut tdo, &mut client, 2, "group/project").unwrap();
+        assert_eq!(link, RemoteIssue::with_forge(Forge::GitLab, "group/project", 4));
+        remote::close(&mut tdo, &mut client, 0).unwrap();
+        assert!(tdo.lists[1].list[0].done);
+        assert_eq!(server.received()[1].method, "PUT");
+    }
+
+    #[test]
+    fn foreign_link_is_not_closed() {
+        let server = MockServer::start(stub);
+        match remote::close(&mut container(), &mut client(&server, "secret"), 1) {
+            Err(Error(ErrorKind::GithubError(github_error::ErrorKind::NoIssueAsigned(1)), _)) => {}
+            other => panic!("expected NoIssueAsigned, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let server = MockServer::start(stub);
+        match remote::sync(&mut container(), &mut client(&server, "wrong")) {
+            Err(Error(ErrorKind::GithubError(github_error::ErrorKind::BadCredentials), _)) => {}
+            other => panic!("expected BadCredentials, got {:?}", other),
+        }
+        match remote::import(&mut container(), &mut client(&server, "secret"), "group/other", "gitlab", &IssueFilter::new()) {
+            Err(Error(ErrorKind::GithubError(github_error::ErrorKind::DoesNotExist(ref repo)), _)) => {
+                assert_eq!(repo, "group/other")
+            }
+            other => panic!("expected DoesNotExist, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rate_limited() {
+        let server = MockServer::start(|_| {
+            Reply::json(429, r#"{"message": "Retry later"}"#)
+                .header("RateLimit-Remaining", "0")
+                .header("RateLimit-Reset", "1700000000")
+        });
+        match remote::sync(&mut container(), &mut client(&server, "secret")) {
+            Err(Error(ErrorKind::GithubError(github_error::ErrorKind::RateLimited(1700000000)), _)) => {}
+            other => panic!("expected RateLimited, got {:?}", other),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod gitea {
+    use serde_json::{self, Value};
+    use common::{MockServer, Received, Reply, TestClient};
+    use tdo_core::{tdo, list, todo};
+    use tdo_core::error::*;
+    use tdo_core::gitea::GiteaClient;
+    use tdo_core::remote::{self, IssueFilter};
+    use tdo_core::todo::{Forge, RemoteIssue};
+
+    fn issue(number: u32, state: &str, title: &str, pull_request: bool) -> String {
+        let pull_request = if pull_request { r#"{"merged": false}"# } else { "null" };
+        format!(r#"{{"id": 500{0}, "number": {0}, "state": "{1}", "title": "{2}",
+                    "html_url": "https://gitea.example.com/me/tdo/issues/{0}", "pull_request": {3}}}"#,
+                number,
+                state,
+                title,
+                pull_request)
+    }
+
+    fn stub(request: &Received) -> Reply {
+        if request.header("Authorization") != Some("token secret") {
+            return Reply::json(401, r#"{"message": "token is required"}"#);
+        }
+        let body: Value = serde_json::from_str(&request.body).unwrap_or(Value::Null);
+        match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/api/v1/repos/me/tdo/issues/5") => Reply::json(200, &issue(5, "closed", "Fixed", false)),
+            ("GET", path) if path.starts_with("/api/v1/repos/me/tdo/issues?") => {
+                Reply::json(200,
+                            &format!("[{}, {}]", issue(6, "open", "New", false), issue(7, "open", "PR", true)))
+            }
+            ("PATCH", "/api/v1/repos/me/tdo/issues/5") => {
+                Reply::json(200, &issue(5, body["state"].as_str().unwrap(), "Fixed", false))
+            }
+            _ => Reply::json(404, r#"{"message": "not found"}"#),
+        }
+    }
+
+    fn container() -> tdo::Tdo {
+        let mut tdo = tdo::Tdo::new();
+        tdo.add_list(list::TodoList::new("gitea")).unwrap();
+        tdo.add_todo(None,
+                      todo::Todo::new(0, "Linked", Some(RemoteIssue::with_forge(Forge::Gitea, "me/tdo", 5))))
+            .unwrap();
+        tdo
+    }
+
+    fn client(server: &MockServer) -> GiteaClient<TestClient> {
+        GiteaClient::new(TestClient, "secret", &format!("{}/api/v1/", server.url()))
+    }
+
+    #[test]
+    fn sync() {
+        let server = MockServer::start(stub);
+        let mut tdo = container();
+        let report = remote::sync(&mut tdo, &mut client(&server)).unwrap();
+        assert_eq!(report.closed, vec![0]);
+        assert_eq!(tdo.lists[0].list[0].name, "Fixed");
+    }
+
+    #[test]
+    fn import_without_pull_requests() {
+        let server = MockServer::start(stub);
+        let mut tdo = container();
+        let report = remote::import(&mut tdo, &mut client(&server), "me/tdo", "gitea", &IssueFilter::new().label("bug"))
+            .unwrap();
+        assert_eq!(report.added, vec![1]);
+        assert_eq!(tdo.lists[1].list[0].github.clone().unwrap(),
+                   RemoteIssue::with_forge(Forge::Gitea, "me/tdo", 6));
+        assert!(server.received()[0].path.contains("type=issues"));
+    }
+
+    #[test]
+    fn close_and_reopen() {
+        let server = MockServer::start(stub);
+        let mut tdo = container();
+        remote::close(&mut tdo, &mut client(&server), 0).unwrap();
+        assert!(tdo.lists[0].list[0].done);
+        remote::reopen(&mut tdo, &mut client(&server), 0).unwrap();
+        assert!(!tdo.lists[0].list[0].done);
+        assert_eq!(server.received()[1].method, "PATCH");
+    }
+
+    #[test]
+    fn missing_repository() {
+        let server = MockServer::start(stub);
+        match remote::import(&mut container(), &mut client(&server), "me/other", "gitea", &IssueFilter::new()) {
+            Err(Error(ErrorKind::GithubError(github_error::ErrorKind::DoesNotExist(_)), _)) => {}
+            other => panic!("expected DoesNotExist, got {:?}", other),
+        }
+    }
+}
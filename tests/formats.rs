@@ -0,0 +1,90 @@
+extern crate tdo_core;
+
+
+#[cfg(test)]
+mod formats {
+    use std::env;
+    use std::process;
+    use tdo_core::{tdo, list, todo};
+    use tdo_core::storage::Format;
+
+    fn sample() -> tdo::Tdo {
+        let mut tdo = tdo::Tdo::new();
+        tdo.add_list(list::TodoList::new("test")).unwrap();
+        tdo.add_todo(Some("test"), todo::Todo::new(0, "First Entry", None)).unwrap();
+        tdo.add_todo(Some("test"),
+                      todo::Todo::new(1, "Issue", Some(todo::GitHub::new("tdolist/tdo", 42))))
+            .unwrap();
+        tdo.add_todo(None, todo::Todo::new(2, "Third Entry", None)).unwrap();
+        tdo.done_id(2).unwrap();
+        tdo.lists[1].list[0].comment("A comment");
+        tdo
+    }
+
+    /// Prefixes `name` with the process id so concurrent test runs don't share files.
+    fn temp_path(name: &str) -> String {
+        let name = format!("{}_{}", process::id(), name);
+        env::temp_dir().join(name).to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn detect_format() {
+        assert_eq!(Format::from_path("tdo.toml"), Format::Toml);
+        assert_eq!(Format::from_path("tdo.YAML"), Format::Yaml);
+        assert_eq!(Format::from_path("tdo.yml"), Format::Yaml);
+        assert_eq!(Format::from_path("tdo.json"), Format::Json);
+        assert_eq!(Format::from_path("tdo"), Format::Json);
+    }
+
+    #[test]
+    #[cfg(not(feature = "toml"))]
+    #[should_panic]
+    fn disabled_format() {
+        sample().save(&temp_path("tdo_disabled.toml")).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "toml")]
+    fn toml_roundtrip() {
+        let path = temp_path("tdo_roundtrip.toml");
+        sample().save(&path).unwrap();
+
+        let loaded = tdo::Tdo::load(&path).unwrap();
+        assert_eq!(loaded.lists[1].list[1].github.as_ref().unwrap().issue_number, 42);
+        assert!(loaded.lists[0].list[0].done);
+        assert_eq!(loaded.lists[1].list[0].comments[0].body, "A comment");
+    }
+
+    #[test]
+    #[cfg(feature = "yaml")]
+    fn yaml_roundtrip() {
+        let path = temp_path("tdo_roundtrip.yaml");
+        sample().save(&path).unwrap();
+
+        let loaded = tdo::Tdo::load(&path).unwrap();
+        assert_eq!(loaded.lists[1].list[1].github.as_ref().unwrap().repo, "tdolist/tdo");
+        assert!(loaded.lists[0].list[0].done);
+    }
+
+    #[test]
+    #[cfg(all(feature = "toml", feature = "yaml"))]
+    fn lossless_conversion() {
+        use tdo_core::storage;
+
+        let json = temp_path("tdo_convert.json");
+        let toml = temp_path("tdo_convert.toml");
+        let yaml = temp_path("tdo_convert.yml");
+        let back = temp_path("tdo_convert_back.json");
+        let mut tdo = sample();
+        tdo.set_gh_token("token");
+        tdo.save(&json).unwrap();
+
+        storage::convert_file(&json, &toml).unwrap();
+        storage::convert_file(&toml, &yaml).unwrap();
+        storage::convert_file(&yaml, &back).unwrap();
+
+        let original = tdo::Tdo::load(&json).unwrap();
+        let converted = tdo::Tdo::load(&back).unwrap();
+        assert_eq!(format!("{:?}", original), format!("{:?}", converted));
+    }
+}
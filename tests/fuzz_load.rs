@@ -0,0 +1,118 @@
+extern crate tdo_core;
+extern crate proptest;
+extern crate serde_json;
+
+
+#[cfg(test)]
+mod fuzz_load {
+    use std::env;
+    use std::fs;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use proptest::prelude::*;
+    use serde_json::{Map, Value};
+    use tdo_core::{migration, tdo};
+    use tdo_core::error::*;
+
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    /// Write the data to a fresh temporary file and load it.
+    fn load(data: &[u8]) -> TdoResult<tdo::Tdo> {
+        let name = format!("tdo_fuzz_{}.json", COUNTER.fetch_add(1, Ordering::SeqCst));
+        let path = env::temp_dir().join(name);
+        fs::write(&path, data).unwrap();
+        let res = tdo::Tdo::load(path.to_str().unwrap());
+        let _ = fs::remove_file(&path);
+        res
+    }
+
+    fn arb_json() -> impl Strategy<Value = Value> {
+        let leaf = prop_oneof![Just(Value::Null),
+                               any::<bool>().prop_map(Value::Bool),
+                               any::<i64>().prop_map(json_number),
+                               "[a-z0-9 .]{0,8}".prop_map(Value::String)];
+        leaf.prop_recursive(4, 64, 6, |inner| {
+            prop_oneof![prop::collection::vec(inner.clone(), 0..6).prop_map(Value::Array),
+                        prop::collection::vec(("(lists|list|version|name|id|done|github|[0-9]{1,3}|[a-z]{1,5})",
+                                               inner),
+                                              0..6)
+                            .prop_map(|entries| Value::Object(entries.into_iter().collect::<Map<_, _>>()))]
+        })
+    }
+
+    fn json_number(n: i64) -> Value {
+        Value::Number(n.into())
+    }
+
+    /// Containers in the pre-0.1 format, with occasionally broken entries.
+    fn arb_legacy() -> impl Strategy<Value = Value> {
+        let entry = prop_oneof![("[a-z ]{0,10}", any::<bool>())
+                                    .prop_map(|(name, done)| Value::Array(vec![Value::String(name), Value::Bool(done)])),
+                                arb_json()];
+        let list = prop::collection::vec(("-?[0-9]{1,4}|[a-z]{1,3}", entry), 0..5)
+            .prop_map(|entries| Value::Object(entries.into_iter().collect::<Map<_, _>>()));
+        prop::collection::vec(("[a-z]{1,8}", list), 0..4)
+            .prop_map(|lists| Value::Object(lists.into_iter().collect::<Map<_, _>>()))
+    }
+
+    proptest! {
+        #[test]
+        fn arbitrary_bytes(data in prop::collection::vec(any::<u8>(), 0..256)) {
+            let _ = load(&data);
+        }
+
+        #[test]
+        fn arbitrary_json(value in arb_json()) {
+            let _ = load(value.to_string().as_bytes());
+        }
+
+        #[test]
+        fn arbitrary_versioned_json(value in arb_json(), version in "[0-9]{1,2}\\.[0-9]{1,2}\\.[0-9]") {
+            let mut value = value;
+            if let Value::Object(ref mut map) = value {
+                map.insert("version".to_string(), Value::String(version));
+            }
+            let _ = load(value.to_string().as_bytes());
+        }
+
+        #[test]
+        fn arbitrary_legacy(value in arb_legacy()) {
+            let report = migration::dry_run(value.clone());
+            match load(value.to_string().as_bytes()) {
+                Ok(tdo) => {
+                    // a successful load must not have dropped anything
+                    let report = report.unwrap();
+                    prop_assert!(report.skipped.is_empty());
+                    prop_assert_eq!(report.todos,
+                                    tdo.lists.iter().map(|list| list.list.len()).sum::<usize>());
+                }
+                Err(Error(ErrorKind::StorageError(storage_error::ErrorKind::InvalidEntry(entry)), _)) => {
+                    prop_assert!(report.unwrap().skipped.contains(&entry));
+                }
+                Err(_) => {}
+            }
+        }
+    }
+
+    #[test]
+    fn vanished_file() {
+        match tdo::Tdo::load("tests/versions/does_not_exist.json") {
+            Err(Error(ErrorKind::StorageError(storage_error::ErrorKind::FileNotFound(ref path)), _)) => {
+                assert_eq!(path, "tests/versions/does_not_exist.json");
+            }
+            other => panic!("expected FileNotFound, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn offending_entry() {
+        let err = tdo::Tdo::load("tests/versions/version00_broken.json").unwrap_err();
+        match *err.kind() {
+            ErrorKind::StorageError(storage_error::ErrorKind::InvalidEntry(ref entry)) => {
+                assert_eq!(entry.list, "default");
+                assert_eq!(entry.entry, Some("two".to_string()));
+            }
+            ref other => panic!("expected InvalidEntry, got {:?}", other),
+        }
+        assert!(err.to_string().contains("two"));
+    }
+}
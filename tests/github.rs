@@ -3,15 +3,62 @@ extern crate tdo_core;
 
 #[cfg(test)]
 mod github {
+    use std::env;
+    use std::fs;
     use tdo_core::tdo;
+    use tdo_core::credentials::*;
 
     #[test]
     fn handle_token() {
         let mut tdo = tdo::Tdo::new();
         let x = tdo.get_gh_token();
         assert_eq!(x, None);
-        tdo.set_gh_token(Some("ThisIsNoWorkingAuthTokenJustForTestingTheMethod"));
+        tdo.set_gh_token("ThisIsNoWorkingAuthTokenJustForTestingTheMethod");
         assert_eq!(tdo.get_gh_token().unwrap(),
                    "ThisIsNoWorkingAuthTokenJustForTestingTheMethod".to_string());
     }
+
+    #[test]
+    fn token_from_store() {
+        let path = env::temp_dir().join("tdo_github_token_store.json");
+        let mut store = FileCredentials::new(path.to_str().unwrap());
+        store.set_token(GITHUB, "StoredToken").unwrap();
+
+        let mut tdo = tdo::Tdo::new();
+        let mut ask = |_: &str, _: Option<&str>| -> Option<String> {
+            panic!("the frontend must not be asked")
+        };
+        tdo.obtain_gh_token(&mut store, &mut ask).unwrap();
+        assert_eq!(tdo.get_gh_token(), Some("StoredToken".to_string()));
+    }
+
+    #[test]
+    fn token_from_provider() {
+        let path = env::temp_dir().join("tdo_github_token_provider.json");
+        let _ = fs::remove_file(&path);
+        let mut store = FileCredentials::new(path.to_str().unwrap());
+
+        let mut tdo = tdo::Tdo::new();
+        let mut asked = vec![];
+        {
+            let mut ask = |service: &str, url: Option<&str>| {
+                asked.push((service.to_string(), url.map(|u| u.to_string())));
+                Some(" EnteredToken\n".to_string())
+            };
+            tdo.obtain_gh_token(&mut store, &mut ask).unwrap();
+        }
+        assert_eq!(asked,
+                   vec![(GITHUB.to_string(), Some(GITHUB_TOKEN_URL.to_string()))]);
+        assert_eq!(tdo.get_gh_token(), Some("EnteredToken".to_string()));
+        // the answer is remembered
+        assert_eq!(store.token(GITHUB).unwrap(), Some("EnteredToken".to_string()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn declined_token() {
+        let mut store = EnvCredentials::with_prefix("TDO_DECLINED");
+        let mut ask = |_: &str, _: Option<&str>| None;
+        tdo::Tdo::new().obtain_gh_token(&mut store, &mut ask).unwrap();
+    }
 }
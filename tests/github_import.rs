@@ -0,0 +1,204 @@
+extern crate tdo_core;
+
+mod common;
+
+
+#[cfg(test)]
+mod import {
+    use std::sync::{Arc, Mutex};
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use common::{MockServer, Received, Reply, TestClient};
+    use tdo_core::{tdo, list, todo};
+    use tdo_core::error::*;
+    use tdo_core::github::GitHubClient;
+    use tdo_core::query::SavedSearch;
+    use tdo_core::remote::{self, IssueFilter};
+
+    fn issue(number: u32, title: &str) -> String {
+        format!(r#"{{"url": "https://api.github.com/repos/tdolist/tdo/issues/{0}",
+                    "html_url": "https://github.com/tdolist/tdo/issues/{0}",
+                    "number": {0}, "state": "open", "title": "{1}"}}"#,
+                number,
+                title)
+    }
+
+    fn pull_request(number: u32) -> String {
+        format!(r#"{{"url": "https://api.github.com/repos/tdolist/tdo/issues/{0}",
+                    "html_url": "https://github.com/tdolist/tdo/issues/{0}",
+                    "number": {0}, "state": "open", "title": "A PR",
+                    "pull_request": {{"url": "https://api.github.com/repos/tdolist/tdo/pulls/{0}"}}}}"#,
+                number)
+    }
+
+    // Serves two pages of issues, the second one with the given title for issue 3.
+    fn paged(request: &Received, third_title: &str) -> Reply {
+        if request.path.contains("page=2") {
+            Reply::json(200, &format!("[{}]", issue(3, third_title)))
+        } else {
+            let next = format!("<http://{}/repos/tdolist/tdo/issues?state=open&page=2>; rel=\"next\", \
+                                <http://{}/repos/tdolist/tdo/issues?state=open&page=2>; rel=\"last\"",
+                               request.header("Host").unwrap(),
+                               request.header("Host").unwrap());
+            Reply::json(200,
+                        &format!("[{}, {}, {}]", issue(1, "First"), pull_request(2), issue(4, "Fourth")))
+                .header("Link", &next)
+        }
+    }
+
+    fn container() -> tdo::Tdo {
+        let mut tdo = tdo::Tdo::new();
+        tdo.add_list(list::TodoList::new("issues")).unwrap();
+        tdo
+    }
+
+    #[test]
+    fn all_pages_without_pull_requests() {
+        let server = MockServer::start(|request| paged(request, "Third"));
+        let mut client = GitHubClient::with_api_url(TestClient, "secret", server.url());
+        let mut tdo = container();
+
+        let report = remote::import(&mut tdo, &mut client, "tdolist/tdo", "issues", &IssueFilter::new())
+            .unwrap();
+        assert_eq!(report.added.len(), 3);
+        assert_eq!(server.received().len(), 2);
+
+        let issues = &tdo.lists[1].list;
+        let numbers: Vec<u32> = issues.iter().map(|todo| todo.github.as_ref().unwrap().issue_number).collect();
+        assert_eq!(numbers, vec![1, 4, 3]);
+        assert_eq!(issues[2].name, "Third");
+        assert_eq!(issues[2].github.as_ref().unwrap().repo, "tdolist/tdo");
+    }
+
+    #[test]
+    fn idempotent() {
+        let title = Arc::new(Mutex::new("Third".to_string()));
+        let current = title.clone();
+        let server = MockServer::start(move |request| paged(request, &current.lock().unwrap()));
+        let mut client = GitHubClient::with_api_url(TestClient, "secret", server.url());
+        let mut tdo = container();
+        tdo.add_todo(None,
+                      todo::Todo::new(0, "First", Some(todo::GitHub::new("tdolist/tdo", 1))))
+            .unwrap();
+
+        let first = remote::import(&mut tdo, &mut client, "tdolist/tdo", "issues", &IssueFilter::new())
+            .unwrap();
+        assert_eq!(first.added, vec![1, 2]);
+        assert_eq!(first.unchanged, 1);
+
+        *title.lock().unwrap() = "Third, renamed".to_string();
+        let second = remote::import(&mut tdo, &mut client, "tdolist/tdo", "issues", &IssueFilter::new())
+            .unwrap();
+        assert!(second.added.is_empty());
+        assert_eq!(second.renamed, vec![2]);
+        assert_eq!(second.unchanged, 2);
+        assert_eq!(tdo.lists[0].list.len(), 1);
+        assert_eq!(tdo.lists[1].list.len(), 2);
+        assert_eq!(tdo.lists[1].list[1].name, "Third, renamed");
+    }
+
+    #[test]
+    fn filters() {
+        let server = MockServer::start(|_| Reply::json(200, "[]"));
+        let mut client = GitHubClient::with_api_url(TestClient, "secret", server.url());
+        let filter = IssueFilter::new().label("bug").label("good first issue").assignee("octocat").milestone("3");
+
+        remote::import(&mut container(), &mut client, "tdolist/tdo", "issues", &filter).unwrap();
+        let path = &server.received()[0].path;
+        assert!(path.starts_with("/repos/tdolist/tdo/issues?state=open"));
+        assert!(path.contains("&labels=bug,good%20first%20issue"));
+        assert!(path.contains("&assignee=octocat"));
+        assert!(path.contains("&milestone=3"));
+    }
+
+    #[test]
+    fn lists_tracking_a_repository() {
+        let server = MockServer::start(|request| {
+            if request.path.contains("labels=bug") {
+                Reply::json(200, &format!("[{}]", issue(5, "Labeled")))
+            } else if request.path == "/repos/tdolist/tdo/issues/5" {
+                Reply::json(200, &issue(5, "Labeled"))
+            } else {
+                Reply::json(200, "[]")
+            }
+        });
+        let mut client = GitHubClient::with_api_url(TestClient, "secret", server.url());
+        let mut tdo = container();
+        tdo.add_list(list::TodoList::for_repo("bugs", "tdolist/tdo").label("bug")).unwrap();
+
+        let report = remote::sync(&mut tdo, &mut client).unwrap();
+        assert_eq!(report.added, vec![1]);
+        assert_eq!(tdo.lists[2].list[0].name, "Labeled");
+        assert!(tdo.lists[1].list.is_empty());
+        assert_eq!(server.received().len(), 1);
+
+        let again = remote::sync(&mut tdo, &mut client).unwrap();
+        assert!(again.added.is_empty());
+        assert_eq!(again.checked, 1);
+        assert_eq!(tdo.lists[2].list.len(), 1);
+    }
+
+    #[test]
+    fn rate_limited() {
+        let server = MockServer::start(|_| {
+            Reply::json(403, r#"{"message": "API rate limit exceeded"}"#)
+                .header("X-RateLimit-Remaining", "0")
+                .header("X-RateLimit-Reset", "1700000000")
+        });
+        let mut client = GitHubClient::with_api_url(TestClient, "secret", server.url());
+        match remote::import(&mut container(), &mut client, "tdolist/tdo", "issues", &IssueFilter::new()) {
+            Err(Error(ErrorKind::GithubError(github_error::ErrorKind::RateLimited(reset)), _)) => {
+                assert_eq!(reset, 1700000000)
+            }
+            other => panic!("expected RateLimited, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn bare_too_many_requests() {
+        let server = MockServer::start(|request| if request.path.contains("tdolist/tdo/") {
+            Reply::json(429, r#"{"message": "Too many requests"}"#)
+        } else {
+            Reply::json(403, r#"{"message": "Forbidden"}"#)
+        });
+        let mut client = GitHubClient::with_api_url(TestClient, "secret", server.url());
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        match remote::import(&mut container(), &mut client, "tdolist/tdo", "issues", &IssueFilter::new()) {
+            Err(Error(ErrorKind::GithubError(github_error::ErrorKind::RateLimited(reset)), _)) => assert!(reset >= now),
+            other => panic!("expected RateLimited, got {:?}", other),
+        }
+        match remote::import(&mut container(), &mut client, "tdolist/private", "issues", &IssueFilter::new()) {
+            Err(Error(ErrorKind::GithubError(github_error::ErrorKind::UnknownError(403)), _)) => {}
+            other => panic!("expected UnknownError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_list() {
+        let server = MockServer::start(|_| Reply::json(200, "[]"));
+        let mut client = GitHubClient::with_api_url(TestClient, "secret", server.url());
+        match remote::import(&mut tdo::Tdo::new(), &mut client, "tdolist/tdo", "issues", &IssueFilter::new()) {
+            Err(Error(ErrorKind::TodoError(todo_error::ErrorKind::NoSuchList(_)), _)) => {}
+            other => panic!("expected NoSuchList, got {:?}", other),
+        }
+        assert!(server.received().is_empty());
+    }
+
+    #[test]
+    fn list_names_ignore_case() {
+        let server = MockServer::start(|_| Reply::json(200, &format!("[{}]", issue(1, "First"))));
+        let mut client = GitHubClient::with_api_url(TestClient, "secret", server.url());
+        let mut tdo = container();
+        tdo.add_search(SavedSearch::new("open issues", "!done repo:tdolist/tdo").unwrap()).unwrap();
+
+        let report = remote::import(&mut tdo, &mut client, "tdolist/tdo", "Default", &IssueFilter::new())
+            .unwrap();
+        assert_eq!(report.added, vec![1]);
+        assert_eq!(tdo.lists[0].list[0].name, "First");
+
+        match remote::import(&mut tdo, &mut client, "tdolist/tdo", "Open Issues", &IssueFilter::new()) {
+            Err(Error(ErrorKind::TodoError(todo_error::ErrorKind::VirtualList(_)), _)) => {}
+            other => panic!("expected VirtualList, got {:?}", other),
+        }
+        assert_eq!(server.received().len(), 1);
+    }
+}
@@ -0,0 +1,234 @@
+extern crate tdo_core;
+extern crate serde_json;
+
+mod common;
+
+
+#[cfg(test)]
+mod issues {
+    use std::time::{Duration, UNIX_EPOCH};
+    use serde_json::{self, Value};
+    use common::{MockServer, Received, Reply, TestClient};
+    use tdo_core::{tdo, list, todo};
+    use tdo_core::error::*;
+    use tdo_core::github::GitHubClient;
+    use tdo_core::http::{HttpClient, Request, Response};
+    use tdo_core::remote::{self, IssueTracker};
+
+    // Answers like GitHub does for creating and updating issues.
+    fn forge(request: &Received) -> Reply {
+        let body: Value = serde_json::from_str(&request.body).unwrap_or(Value::Null);
+        match (request.method.as_str(), request.path.as_str()) {
+            ("POST", "/repos/tdolist/tdo/issues") => {
+                Reply::json(201,
+                            &json_issue(7, "open", body["title"].as_str().unwrap()))
+            }
+            ("PATCH", "/repos/tdolist/tdo/issues/7") => {
+                Reply::json(200, &json_issue(7, body["state"].as_str().unwrap(), "Title"))
+            }
+            ("POST", "/repos/tdolist/tdo/issues/7/comments") => Reply::json(201, "{}"),
+            ("GET", "/repos/tdolist/tdo/issues/7/comments?per_page=100") => {
+                Reply::json(200,
+                            r#"[{"id": 11, "user": {"login": "octocat"}, "body": "Can reproduce",
+                                 "created_at": "2026-10-01T08:00:00Z"},
+                                {"id": 12, "user": {"login": "tdo-user"}, "body": "Fixed locally",
+                                 "created_at": "2026-10-02T10:30:00.250+02:00"}]"#)
+            }
+            _ => Reply::json(404, r#"{"message": "Not Found"}"#),
+        }
+    }
+
+    fn json_issue(number: u32, state: &str, title: &str) -> String {
+        format!(r#"{{"url": "https://api.github.com/repos/tdolist/tdo/issues/{0}",
+                    "html_url": "https://github.com/tdolist/tdo/issues/{0}",
+                    "number": {0}, "state": "{1}", "title": "{2}"}}"#,
+                number,
+                state,
+                title)
+    }
+
+    fn container() -> tdo::Tdo {
+        let mut tdo = tdo::Tdo::new();
+        tdo.add_list(list::TodoList::new("work")).unwrap();
+        tdo.add_todo(Some("work"), todo::Todo::new(0, "Local todo", None)).unwrap();
+        tdo.add_todo(None,
+                      todo::Todo::new(1, "Linked todo", Some(todo::GitHub::new("tdolist/tdo", 7))))
+            .unwrap();
+        tdo
+    }
+
+    #[test]
+    fn promote() {
+        let server = MockServer::start(forge);
+        let mut client = GitHubClient::with_api_url(TestClient, "secret", server.url());
+        let mut tdo = container();
+
+        let github = remote::promote(&mut tdo, &mut client, 0, "tdolist/tdo").unwrap();
+        assert_eq!(github.issue_number, 7);
+        let linked = tdo.lists[1].list[0].github.as_ref().unwrap();
+        assert_eq!(linked.repo, "tdolist/tdo");
+        assert_eq!(linked.issue_number, 7);
+
+        let request = &server.received()[0];
+        assert_eq!(request.method, "POST");
+        let body: Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(body["title"], "Local todo");
+    }
+
+    #[test]
+    fn publish() {
+        let server = MockServer::start(forge);
+        let mut client = GitHubClient::with_api_url(TestClient, "secret", server.url());
+        let mut tdo = container();
+        tdo.add_list(list::TodoList::for_repo("bugs", "tdolist/tdo").label("bug").label("ui")).unwrap();
+        tdo.add_todo(Some("bugs"), todo::Todo::new(2, "Filed from a list", None)).unwrap();
+
+        let github = remote::publish(&mut tdo, &mut client, 2).unwrap();
+        assert_eq!(github, todo::GitHub::new("tdolist/tdo", 7));
+        assert_eq!(tdo.lists[2].list[0].github, Some(github));
+
+        let body: Value = serde_json::from_str(&server.received()[0].body).unwrap();
+        assert_eq!(body["title"], "Filed from a list");
+        assert_eq!(body["labels"], serde_json::json!(["bug", "ui"]));
+    }
+
+    #[test]
+    fn publish_without_repository() {
+        let server = MockServer::start(forge);
+        let mut client = GitHubClient::with_api_url(TestClient, "secret", server.url());
+        match remote::publish(&mut container(), &mut client, 0) {
+            Err(Error(ErrorKind::GithubError(github_error::ErrorKind::NoRepository(list)), _)) => assert_eq!(list, "work"),
+            other => panic!("expected NoRepository, got {:?}", other),
+        }
+        assert!(server.received().is_empty());
+    }
+
+    #[test]
+    fn browser_url() {
+        let server = MockServer::start(forge);
+        let mut client = GitHubClient::with_api_url(TestClient, "secret", server.url());
+        let issue = client.create_issue("tdolist/tdo", "Title", &[]).unwrap();
+        assert_eq!(issue.url, "https://github.com/tdolist/tdo/issues/7");
+    }
+
+    #[test]
+    fn promote_linked() {
+        let server = MockServer::start(forge);
+        let mut client = GitHubClient::with_api_url(TestClient, "secret", server.url());
+        match remote::promote(&mut container(), &mut client, 1, "tdolist/tdo") {
+            Err(Error(ErrorKind::GithubError(github_error::ErrorKind::AlreadyLinked(id)), _)) => assert_eq!(id, 1),
+            other => panic!("expected AlreadyLinked, got {:?}", other),
+        }
+        assert!(server.received().is_empty());
+    }
+
+    #[test]
+    fn close_and_reopen() {
+        let server = MockServer::start(forge);
+        let mut client = GitHubClient::with_api_url(TestClient, "secret", server.url());
+        let mut tdo = container();
+
+        remote::close(&mut tdo, &mut client, 1).unwrap();
+        assert!(tdo.lists[0].list[0].done);
+        remote::reopen(&mut tdo, &mut client, 1).unwrap();
+        assert!(!tdo.lists[0].list[0].done);
+
+        let received = server.received();
+        assert_eq!(received[0].method, "PATCH");
+        assert!(received[0].body.contains("closed"));
+        assert!(received[1].body.contains("open"));
+    }
+
+    #[test]
+    fn failed_close_keeps_todo() {
+        let server = MockServer::start(|_| Reply::json(401, r#"{"message": "Bad credentials"}"#));
+        let mut client = GitHubClient::with_api_url(TestClient, "wrong", server.url());
+        let mut tdo = container();
+
+        assert!(remote::close(&mut tdo, &mut client, 1).is_err());
+        assert!(!tdo.lists[0].list[0].done);
+    }
+
+    #[test]
+    fn mirror_comments() {
+        let server = MockServer::start(forge);
+        let mut client = GitHubClient::with_api_url(TestClient, "secret", server.url());
+        let mut tdo = container();
+        remote::comment(&mut tdo, &mut client, 1, "Fixed locally").unwrap();
+
+        assert_eq!(remote::mirror_comments(&mut tdo, &mut client, 1).unwrap(), 1);
+        assert_eq!(remote::mirror_comments(&mut tdo, &mut client, 1).unwrap(), 0);
+        let comments = &tdo.lists[0].list[0].comments;
+        assert_eq!(comments.len(), 2);
+        assert_eq!(comments[0].body, "Can reproduce");
+        assert_eq!(comments[0].author, Some("octocat".to_string()));
+        assert_eq!(comments[0].created, UNIX_EPOCH + Duration::from_secs(1790841600));
+        assert_eq!(comments[1].remote_id, Some(12));
+        assert_eq!(comments[1].author, Some("tdo-user".to_string()));
+    }
+
+    #[test]
+    fn mirror_duplicate_comments() {
+        let server = MockServer::start(|_| {
+            Reply::json(200,
+                        r#"[{"id": 21, "user": {"login": "octocat"}, "body": "+1",
+                             "created_at": "2026-10-01T08:00:00Z"},
+                            {"id": 22, "user": {"login": "tdo-user"}, "body": "+1",
+                             "created_at": "2026-10-02T08:00:00Z"},
+                            {"id": 23, "user": {"login": "hubot"}, "body": "+1",
+                             "created_at": "2026-10-03T08:00:00Z"}]"#)
+        });
+        let mut client = GitHubClient::with_api_url(TestClient, "secret", server.url());
+        let mut tdo = container();
+        tdo.lists[0].list[0].comment("+1");
+        tdo.lists[0].list[0].comment("+1");
+        tdo.lists[0].list[0].comments[0].remote_id = Some(22);
+
+        assert_eq!(remote::mirror_comments(&mut tdo, &mut client, 1).unwrap(), 1);
+        assert_eq!(remote::mirror_comments(&mut tdo, &mut client, 1).unwrap(), 0);
+        let mut ids: Vec<_> = tdo.lists[0].list[0].comments.iter().map(|comment| comment.remote_id).collect();
+        ids.sort();
+        assert_eq!(ids, vec![Some(21), Some(22), Some(23)]);
+    }
+
+    #[test]
+    fn close_unlinked() {
+        let server = MockServer::start(forge);
+        let mut client = GitHubClient::with_api_url(TestClient, "secret", server.url());
+        match remote::close(&mut container(), &mut client, 0) {
+            Err(Error(ErrorKind::GithubError(github_error::ErrorKind::NoIssueAsigned(id)), _)) => assert_eq!(id, 0),
+            other => panic!("expected NoIssueAsigned, got {:?}", other),
+        }
+    }
+
+    // Answers every request with a closed issue and records the URLs it was asked for.
+    struct Recorder(Vec<String>);
+
+    impl HttpClient for Recorder {
+        fn send(&mut self, request: &Request) -> TdoResult<Response> {
+            self.0.push(format!("{} {}", request.method.as_str(), request.url));
+            Ok(Response {
+                status: 200,
+                headers: vec![],
+                body: json_issue(7, "closed", "Linked todo"),
+            })
+        }
+    }
+
+    #[test]
+    fn done_with_close() {
+        let mut tdo = container();
+        tdo.set_gh_token("secret");
+        let mut http = Recorder(vec![]);
+
+        assert_eq!(tdo.done_id_with(&mut http, 0, true).unwrap(), None);
+        assert_eq!(tdo.done_id_with(&mut http, 1, false).unwrap(), None);
+        assert!(http.0.is_empty());
+        tdo.undone_id(1).unwrap();
+
+        assert!(tdo.done_id_with(&mut http, 1, true).unwrap().is_some());
+        assert!(tdo.lists[0].list[0].done);
+        assert!(tdo.lists[1].list[0].done);
+        assert_eq!(http.0, vec!["PATCH https://api.github.com/repos/tdolist/tdo/issues/7"]);
+    }
+}
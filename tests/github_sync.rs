@@ -0,0 +1,232 @@
+extern crate tdo_core;
+
+mod common;
+
+
+#[cfg(test)]
+mod sync {
+    use std::net::TcpListener;
+    use common::{MockServer, Reply, TestClient};
+    use tdo_core::{tdo, list, todo};
+    use tdo_core::error::*;
+    use tdo_core::github::GitHubClient;
+    #[cfg(feature = "http")]
+    use tdo_core::http::UreqClient;
+    use tdo_core::remote::{self, IssueTracker};
+
+    fn issue(number: u32, state: &str, title: &str) -> String {
+        format!(r#"{{"url": "https://api.github.com/repos/tdolist/tdo/issues/{0}",
+                    "html_url": "https://github.com/tdolist/tdo/issues/{0}",
+                    "number": {0}, "state": "{1}", "title": "{2}", "comments": 0}}"#,
+                number,
+                state,
+                title)
+    }
+
+    fn linked() -> tdo::Tdo {
+        let mut tdo = tdo::Tdo::new();
+        tdo.add_list(list::TodoList::new("local")).unwrap();
+        tdo.add_todo(None,
+                      todo::Todo::new(0, "Open issue", Some(todo::GitHub::new("tdolist/tdo", 1))))
+            .unwrap();
+        tdo.add_todo(None,
+                      todo::Todo::new(1, "Old title", Some(todo::GitHub::new("tdolist/tdo", 2))))
+            .unwrap();
+        tdo.add_todo(Some("local"), todo::Todo::new(2, "Not linked", None)).unwrap();
+        tdo
+    }
+
+    #[test]
+    fn closed_and_renamed() {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/repos/tdolist/tdo/issues/1" => Reply::json(200, &issue(1, "open", "Open issue")),
+            "/repos/tdolist/tdo/issues/2" => Reply::json(200, &issue(2, "closed", "New title")),
+            _ => Reply::json(404, r#"{"message": "Not Found"}"#),
+        });
+        let mut tdo = linked();
+        let mut client = GitHubClient::with_api_url(TestClient, "secret", server.url());
+
+        let report = remote::sync(&mut tdo, &mut client).unwrap();
+        assert_eq!(report.checked, 2);
+        assert_eq!(report.closed, vec![1]);
+        assert_eq!(report.renamed, vec![1]);
+        assert!(!tdo.lists[0].list[0].done);
+        assert!(tdo.lists[0].list[1].done);
+        assert_eq!(tdo.lists[0].list[1].name, "New title");
+        assert_eq!(tdo.lists[1].list[0].name, "Not linked");
+
+        let received = server.received();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0].method, "GET");
+        assert_eq!(received[0].header("Authorization"), Some("token secret"));
+        assert!(received[0].header("User-Agent").unwrap().starts_with("tdo-core/"));
+    }
+
+    #[test]
+    #[cfg(feature = "http")]
+    fn ureq_client() {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/repos/tdolist/tdo/issues/1" => Reply::json(200, &issue(1, "closed", "Open issue")),
+            _ => Reply::json(200, &issue(2, "open", "Old title")),
+        });
+        let mut tdo = linked();
+        let mut client = GitHubClient::with_api_url(UreqClient::new(), "secret", server.url());
+
+        let report = remote::sync(&mut tdo, &mut client).unwrap();
+        assert_eq!(report.closed, vec![0]);
+        assert_eq!(server.received()[0].header("Authorization"), Some("token secret"));
+    }
+
+    #[test]
+    fn done_todos_stay_done() {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/repos/tdolist/tdo/issues/1" => Reply::json(200, &issue(1, "open", "Open issue")),
+            _ => Reply::json(200, &issue(2, "open", "Old title")),
+        });
+        let mut tdo = linked();
+        tdo.done_id(0).unwrap();
+        let mut client = GitHubClient::with_api_url(TestClient, "secret", server.url());
+
+        let report = remote::sync(&mut tdo, &mut client).unwrap();
+        assert!(report.closed.is_empty() && report.renamed.is_empty());
+        assert!(tdo.lists[0].list[0].done);
+    }
+
+    fn pull(number: u32, state: &str, draft: bool, merged: bool) -> String {
+        format!(r#"{{"html_url": "https://github.com/tdolist/tdo/pull/{0}", "number": {0},
+                    "state": "{1}", "title": "A PR", "draft": {2}, "merged": {3}}}"#,
+                number,
+                state,
+                draft,
+                merged)
+    }
+
+    #[test]
+    fn merged_pull_requests() {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/repos/tdolist/tdo/pulls/5" => Reply::json(200, &pull(5, "closed", false, true)),
+            "/repos/tdolist/tdo/pulls/6" => Reply::json(200, &pull(6, "open", true, false)),
+            "/repos/tdolist/tdo/pulls/7" => Reply::json(200, &pull(7, "closed", false, false)),
+            _ => Reply::json(404, r#"{"message": "Not Found"}"#),
+        });
+        let mut tdo = tdo::Tdo::new();
+        for (id, number) in [(0, 5), (1, 6), (2, 7)] {
+            let mut todo = todo::Todo::new(id, "Review", None);
+            todo.pull_request = Some(todo::PullRequest::new("tdolist/tdo", number));
+            tdo.add_todo(None, todo).unwrap();
+        }
+        let mut todo = todo::Todo::new(3, "Cherry-pick", None);
+        todo.commit = Some(todo::Commit::new("tdolist/tdo", "1f2e3d4"));
+        tdo.add_todo(None, todo).unwrap();
+        let mut client = GitHubClient::with_api_url(TestClient, "secret", server.url());
+
+        let report = remote::sync(&mut tdo, &mut client).unwrap();
+        assert_eq!(report.checked, 3);
+        assert_eq!(report.merged, vec![0]);
+        assert!(report.renamed.is_empty());
+        let todos = &tdo.lists[0].list;
+        let states: Vec<todo::PullState> = todos[..3].iter().map(|todo| todo.pull_request.as_ref().unwrap().state).collect();
+        assert_eq!(states,
+                   vec![todo::PullState::Merged, todo::PullState::Draft, todo::PullState::Closed]);
+        assert!(todos[0].done);
+        assert!(!todos[1].done && !todos[2].done && !todos[3].done);
+        assert_eq!(todos[0].name, "Review");
+        assert_eq!(server.received().len(), 3);
+    }
+
+    #[test]
+    fn conditional_requests() {
+        let server = MockServer::start(|request| match request.header("If-None-Match") {
+            Some("\"v1\"") => Reply::json(304, ""),
+            _ => {
+                Reply::json(200, &issue(1, "open", "Open issue"))
+                    .header("ETag", "\"v1\"")
+                    .header("Last-Modified", "Sun, 18 Oct 2026 10:00:00 GMT")
+            }
+        });
+        let mut tdo = tdo::Tdo::new();
+        tdo.add_todo(None,
+                      todo::Todo::new(0, "Open issue", Some(todo::GitHub::new("tdolist/tdo", 1))))
+            .unwrap();
+        let mut client = GitHubClient::with_api_url(TestClient, "secret", server.url());
+
+        let first = remote::sync(&mut tdo, &mut client).unwrap();
+        assert_eq!(first.not_modified, 0);
+        let cache = tdo.lists[0].list[0].github.as_ref().unwrap().cache.clone();
+        assert_eq!(cache.etag, Some("\"v1\"".to_string()));
+        assert_eq!(cache.last_modified, Some("Sun, 18 Oct 2026 10:00:00 GMT".to_string()));
+
+        tdo.lists[0].list[0].edit("Renamed locally");
+        let second = remote::sync(&mut tdo, &mut client).unwrap();
+        assert_eq!(second.checked, 1);
+        assert_eq!(second.not_modified, 1);
+        assert!(second.renamed.is_empty());
+        assert_eq!(tdo.lists[0].list[0].name, "Renamed locally");
+
+        let received = server.received();
+        assert_eq!(received[0].header("If-None-Match"), None);
+        assert_eq!(received[1].header("If-None-Match"), Some("\"v1\""));
+        assert_eq!(received[1].header("If-Modified-Since"), Some("Sun, 18 Oct 2026 10:00:00 GMT"));
+    }
+
+    #[test]
+    fn rate_limit() {
+        let server = MockServer::start(|request| {
+            let reply = if request.path.ends_with("/1") {
+                Reply::json(200, &issue(1, "open", "Open issue")).header("X-RateLimit-Remaining", "1")
+            } else {
+                Reply::json(403, r#"{"message": "API rate limit exceeded"}"#).header("X-RateLimit-Remaining", "0")
+            };
+            reply.header("X-RateLimit-Limit", "60").header("X-RateLimit-Reset", "1700000000")
+        });
+        let mut client = GitHubClient::with_api_url(TestClient, "secret", server.url());
+        assert_eq!(client.rate_limit(), None);
+        match remote::sync(&mut linked(), &mut client) {
+            Err(Error(ErrorKind::GithubError(github_error::ErrorKind::RateLimited(1700000000)), _)) => {}
+            other => panic!("expected RateLimited, got {:?}", other),
+        }
+        let rate_limit = client.rate_limit().unwrap();
+        assert_eq!((rate_limit.limit, rate_limit.remaining, rate_limit.reset), (60, 0, 1700000000));
+    }
+
+    #[test]
+    fn missing_repository() {
+        let server = MockServer::start(|_| Reply::json(404, r#"{"message": "Not Found"}"#));
+        let mut client = GitHubClient::with_api_url(TestClient, "secret", server.url());
+        match remote::sync(&mut linked(), &mut client) {
+            Err(Error(ErrorKind::GithubError(github_error::ErrorKind::DoesNotExist(ref repo)), _)) => {
+                assert_eq!(repo, "tdolist/tdo")
+            }
+            other => panic!("expected DoesNotExist, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn bad_credentials() {
+        let server = MockServer::start(|_| Reply::json(401, r#"{"message": "Bad credentials"}"#));
+        let mut client = GitHubClient::with_api_url(TestClient, "wrong", server.url());
+        match remote::sync(&mut linked(), &mut client) {
+            Err(Error(ErrorKind::GithubError(github_error::ErrorKind::BadCredentials), _)) => {}
+            other => panic!("expected BadCredentials, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unreachable_server() {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let url = format!("http://127.0.0.1:{}", port);
+        let mut client = GitHubClient::with_api_url(TestClient, "secret", &url);
+        match remote::sync(&mut linked(), &mut client) {
+            Err(Error(ErrorKind::GithubError(github_error::ErrorKind::RequestFailed(_)), _)) => {}
+            other => panic!("expected RequestFailed, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_token() {
+        match linked().sync_github(TestClient) {
+            Err(Error(ErrorKind::GithubError(github_error::ErrorKind::MissingToken), _)) => {}
+            other => panic!("expected MissingToken, got {:?}", other),
+        }
+    }
+}
@@ -104,4 +104,64 @@ mod counter {
         // make sure the new element is at the  position
         assert_eq!(tdo.lists[new_list].list[1].id, 3);
     }
+
+    fn linked() -> tdo::Tdo {
+        let mut tdo = tdo::Tdo::new();
+        tdo.add_list(list::TodoList::for_repo("tdo", "tdolist/tdo")).unwrap();
+        tdo.add_list(list::TodoList::for_repo("core", "tdolist/tdo-core")).unwrap();
+        tdo.add_list(list::TodoList::new("local")).unwrap();
+        tdo.add_todo(None,
+                      todo::Todo::new(0, "Issue", Some(todo::GitHub::new("tdolist/tdo", 1))))
+            .unwrap();
+        tdo
+    }
+
+    #[test]
+    fn move_policy_locked() {
+        let mut tdo = linked();
+        assert_eq!(tdo.move_policy, tdo::MovePolicy::Locked);
+        for target in &["tdo", "core", "local"] {
+            assert!(tdo.move_todo(0, target).is_err());
+        }
+        assert_eq!(tdo.lists[0].list.len(), 1);
+    }
+
+    #[test]
+    fn move_policy_repo_bound() {
+        let mut tdo = linked();
+        tdo.move_policy = tdo::MovePolicy::RepoBound;
+        assert!(tdo.move_todo(0, "core").is_err());
+        assert!(tdo.move_todo(0, "local").is_err());
+        tdo.move_todo(0, "tdo").unwrap();
+        assert_eq!(tdo.lists[1].list[0].id, 0);
+        assert!(tdo.move_todo(0, "default").is_err());
+    }
+
+    #[test]
+    fn move_policy_repo_bound_forge() {
+        let mut tdo = linked();
+        tdo.move_policy = tdo::MovePolicy::RepoBound;
+        tdo.add_list(list::TodoList::for_repo("gitlab", "tdolist/tdo").with_forge(todo::Forge::GitLab)).unwrap();
+        tdo.add_todo(None,
+                      todo::Todo::new(1,
+                                      "GitLab issue",
+                                      Some(todo::RemoteIssue::with_forge(todo::Forge::GitLab, "tdolist/tdo", 2))))
+            .unwrap();
+
+        assert!(tdo.move_todo(0, "gitlab").is_err());
+        assert!(tdo.move_todo(1, "tdo").is_err());
+        tdo.move_todo(0, "tdo").unwrap();
+        tdo.move_todo(1, "gitlab").unwrap();
+        assert_eq!(tdo.lists[1].list[0].id, 0);
+        assert_eq!(tdo.lists[4].list[0].id, 1);
+    }
+
+    #[test]
+    fn move_policy_free() {
+        let mut tdo = linked();
+        tdo.move_policy = tdo::MovePolicy::Free;
+        tdo.move_todo(0, "local").unwrap();
+        tdo.move_todo(0, "core").unwrap();
+        assert_eq!(tdo.lists[2].list[0].id, 0);
+    }
 }
@@ -6,6 +6,7 @@ mod storage {
     use tdo_core::{tdo, list, todo};
 
     #[test]
+    #[allow(clippy::let_unit_value)]
     fn save() {
         let mut tdo = tdo::Tdo::new();
         tdo.add_list(list::TodoList::new("test")).unwrap();
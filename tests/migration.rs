@@ -0,0 +1,81 @@
+extern crate tdo_core;
+#[macro_use]
+extern crate serde_json;
+
+
+#[cfg(test)]
+mod migration {
+    use std::env;
+    use tdo_core::{storage, tdo};
+    use tdo_core::error::*;
+    use tdo_core::migration::*;
+
+    #[test]
+    fn registry_is_complete() {
+        let steps = migrations();
+        assert_eq!(steps.first().unwrap().from, "0.0");
+        assert_eq!(steps.last().unwrap().to, current_schema());
+        for pair in steps.windows(2) {
+            assert_eq!(pair[0].to, pair[1].from);
+        }
+    }
+
+    #[test]
+    fn load_pre01() {
+        let (tdo, steps) = storage::load_migrated("tests/versions/version00.json").unwrap();
+        let ran: Vec<_> = steps.iter().map(|step| step.from).collect();
+        assert_eq!(ran, vec!["0.0", "0.1", "0.2"]);
+
+        assert_eq!(tdo.lists.len(), 2);
+        assert_eq!(tdo.lists[0].name, "default");
+        assert_eq!(tdo.lists[0].list[1].name, "Write thesis");
+        assert!(tdo.lists[0].list[1].done);
+        assert_eq!(tdo.lists[1].list[0].id, 3);
+    }
+
+    #[test]
+    fn load_01() {
+        let (tdo, steps) = storage::load_migrated("tests/versions/version01.json").unwrap();
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0].to, "0.2");
+        assert_eq!(tdo.get_gh_token(), None);
+    }
+
+    #[test]
+    fn current_version_needs_no_steps() {
+        let path = env::temp_dir().join("tdo_migration_current.json");
+        let path = path.to_str().unwrap();
+        tdo::Tdo::new().save(path).unwrap();
+
+        let (_, steps) = storage::load_migrated(path).unwrap();
+        assert!(steps.is_empty());
+    }
+
+    #[test]
+    fn newer_version() {
+        match tdo::Tdo::load("tests/versions/version99.json") {
+            Err(Error(ErrorKind::StorageError(storage_error::ErrorKind::UnsupportedVersion(version)),
+                      _)) => assert_eq!(version, "99.0.0"),
+            other => panic!("expected UnsupportedVersion, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn pre01_step() {
+        let step = migrations()[0];
+        let converted = step.apply(json!({ "test": { "7": ["Entry", true] } })).unwrap();
+        assert_eq!(converted["version"], "0.1.0");
+        assert_eq!(converted["lists"][0]["list"][0]["id"], 7);
+
+        assert!(step.apply(json!({ "test": { "seven": ["Entry", true] } })).is_err());
+        assert!(step.apply(json!({ "test": { "7": ["Entry"] } })).is_err());
+    }
+
+    #[test]
+    fn steps_are_reported() {
+        let migrated = migrate(json!({ "lists": [], "version": "0.1.1" })).unwrap();
+        assert_eq!(migrated.source_version, "0.1.1");
+        assert_eq!(migrated.steps.len(), 2);
+        assert!(migrated.value.get("access_token").is_none());
+    }
+}
@@ -0,0 +1,221 @@
+extern crate tdo_core;
+extern crate serde_json;
+
+mod common;
+
+
+#[cfg(test)]
+mod outbox {
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use serde_json::{self, Value};
+    use common::{MockServer, Received, Reply, TestClient};
+    use tdo_core::{tdo, todo};
+    use tdo_core::error::*;
+    use tdo_core::github::GitHubClient;
+    use tdo_core::outbox::{self, Delivery, Operation, RetryPolicy};
+    use tdo_core::remote;
+
+    fn issue(state: &str, title: &str) -> String {
+        format!(r#"{{"url": "https://api.github.com/repos/tdolist/tdo/issues/7",
+                    "html_url": "https://github.com/tdolist/tdo/issues/7",
+                    "number": 7, "state": "{}", "title": "{}"}}"#,
+                state,
+                title)
+    }
+
+    // An issue that is open and still has its original title.
+    fn unchanged(request: &Received) -> Reply {
+        let body: Value = serde_json::from_str(&request.body).unwrap_or(Value::Null);
+        match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/repos/tdolist/tdo/issues/7") => Reply::json(200, &issue("open", "Linked todo")),
+            ("PATCH", "/repos/tdolist/tdo/issues/7") => {
+                Reply::json(200,
+                            &issue(body["state"].as_str().unwrap_or("open"),
+                                   body["title"].as_str().unwrap_or("Linked todo")))
+            }
+            ("POST", "/repos/tdolist/tdo/issues/7/comments") => Reply::json(201, r#"{"id": 1}"#),
+            _ => Reply::json(404, r#"{"message": "Not Found"}"#),
+        }
+    }
+
+    fn offline() -> GitHubClient<TestClient> {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        GitHubClient::with_api_url(TestClient, "secret", &format!("http://127.0.0.1:{}", port))
+    }
+
+    fn online(server: &MockServer) -> GitHubClient<TestClient> {
+        GitHubClient::with_api_url(TestClient, "secret", server.url())
+    }
+
+    fn container() -> tdo::Tdo {
+        let mut tdo = tdo::Tdo::new();
+        tdo.add_todo(None,
+                      todo::Todo::new(1, "Linked todo", Some(todo::GitHub::new("tdolist/tdo", 7))))
+            .unwrap();
+        tdo
+    }
+
+    fn no_delay(attempts: u32) -> RetryPolicy {
+        RetryPolicy::new(attempts, Duration::from_millis(0))
+    }
+
+    #[test]
+    fn queue_while_offline() {
+        let mut tdo = container();
+        assert_eq!(remote::close(&mut tdo, &mut offline(), 1).unwrap(), Delivery::Queued);
+        assert!(tdo.lists[0].list[0].done);
+        assert_eq!(tdo.outbox.len(), 1);
+        assert_eq!(tdo.outbox[0].operation, Operation::Close);
+        assert_eq!(tdo.outbox[0].attempts, 1);
+
+        let server = MockServer::start(unchanged);
+        let report = outbox::flush(&mut tdo, &mut online(&server), &no_delay(1)).unwrap();
+        assert_eq!(report.sent, 1);
+        assert!(tdo.outbox.is_empty());
+        let received = server.received();
+        assert_eq!(received[1].method, "PATCH");
+        assert!(received[1].body.contains("closed"));
+    }
+
+    #[test]
+    fn keep_order() {
+        let server = MockServer::start(unchanged);
+        let mut tdo = container();
+        remote::close(&mut tdo, &mut offline(), 1).unwrap();
+        assert_eq!(remote::comment(&mut tdo, &mut online(&server), 1, "Later").unwrap(),
+                   Delivery::Queued);
+        assert!(server.received().is_empty());
+
+        let report = outbox::flush(&mut tdo, &mut online(&server), &no_delay(1)).unwrap();
+        assert_eq!(report.sent, 2);
+        let received = server.received();
+        assert_eq!(received[1].method, "PATCH");
+        assert_eq!(received[2].path, "/repos/tdolist/tdo/issues/7/comments");
+        assert!(received[2].body.contains("Later"));
+    }
+
+    #[test]
+    fn send_when_online() {
+        let server = MockServer::start(unchanged);
+        let mut tdo = container();
+        assert_eq!(remote::retitle(&mut tdo, &mut online(&server), 1, "New title").unwrap(),
+                   Delivery::Sent);
+        assert_eq!(tdo.lists[0].list[0].name, "New title");
+        assert!(tdo.outbox.is_empty());
+        assert!(server.received()[0].body.contains("New title"));
+    }
+
+    #[test]
+    fn conflicts() {
+        let server = MockServer::start(|_| Reply::json(200, &issue("closed", "Renamed upstream")));
+        let mut tdo = container();
+        remote::retitle(&mut tdo, &mut offline(), 1, "New title").unwrap();
+        remote::close(&mut tdo, &mut offline(), 1).unwrap();
+
+        let report = outbox::flush(&mut tdo, &mut online(&server), &no_delay(1)).unwrap();
+        assert_eq!(report.sent, 0);
+        assert_eq!(report.already_applied, 1);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].remote.title, "Renamed upstream");
+        assert_eq!(report.conflicts[0].pending.operation,
+                   Operation::Retitle {
+                       from: "Linked todo".to_string(),
+                       to: "New title".to_string(),
+                   });
+        assert!(tdo.outbox.is_empty());
+        assert!(server.received().iter().all(|request| request.method == "GET"));
+    }
+
+    #[test]
+    fn rate_limit_stops_flush() {
+        let requests = AtomicUsize::new(0);
+        let server = MockServer::start(move |request| if requests.fetch_add(1, Ordering::SeqCst) < 1 {
+            Reply::json(429, r#"{"message": "Slow down"}"#).header("Retry-After", "0")
+        } else {
+            unchanged(request)
+        });
+        let mut tdo = container();
+        remote::reopen(&mut tdo, &mut offline(), 1).unwrap();
+
+        match outbox::flush(&mut tdo, &mut online(&server), &no_delay(3)) {
+            Err(Error(ErrorKind::GithubError(github_error::ErrorKind::RateLimited(_)), _)) => {}
+            other => panic!("expected RateLimited, got {:?}", other),
+        }
+        assert_eq!(tdo.outbox.len(), 1);
+        assert_eq!(server.received().len(), 1);
+
+        let report = outbox::flush(&mut tdo, &mut online(&server), &no_delay(3)).unwrap();
+        assert_eq!(report.already_applied, 1);
+        assert!(tdo.outbox.is_empty());
+        assert_eq!(server.received().len(), 2);
+    }
+
+    #[test]
+    fn give_up() {
+        let mut tdo = container();
+        remote::close(&mut tdo, &mut offline(), 1).unwrap();
+        match outbox::flush(&mut tdo, &mut offline(), &no_delay(2)) {
+            Err(Error(ErrorKind::GithubError(github_error::ErrorKind::RequestFailed(_)), _)) => {}
+            other => panic!("expected RequestFailed, got {:?}", other),
+        }
+        assert_eq!(tdo.outbox.len(), 1);
+        assert_eq!(tdo.outbox[0].attempts, 3);
+    }
+
+    #[test]
+    fn rejected_operations_are_dropped() {
+        let server = MockServer::start(unchanged);
+        let mut tdo = container();
+        tdo.add_todo(None,
+                      todo::Todo::new(2, "Deleted issue", Some(todo::GitHub::new("tdolist/tdo", 8))))
+            .unwrap();
+        remote::close(&mut tdo, &mut offline(), 2).unwrap();
+        remote::close(&mut tdo, &mut offline(), 1).unwrap();
+
+        let report = outbox::flush(&mut tdo, &mut online(&server), &no_delay(1)).unwrap();
+        assert_eq!(report.sent, 1);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].pending.todo_id, 2);
+        assert_eq!(report.failed[0].error.code, "github.does_not_exist");
+        assert!(tdo.outbox.is_empty());
+        assert!(server.received()
+            .iter()
+            .any(|request| request.method == "PATCH" && request.path == "/repos/tdolist/tdo/issues/7"));
+    }
+
+    #[test]
+    fn invalid_responses_are_not_queued() {
+        let server = MockServer::start(|_| Reply::json(200, "<html>Maintenance</html>"));
+        let mut tdo = container();
+        match remote::close(&mut tdo, &mut online(&server), 1) {
+            Err(Error(ErrorKind::GithubError(github_error::ErrorKind::InvalidResponse(_)), _)) => {}
+            other => panic!("expected InvalidResponse, got {:?}", other),
+        }
+        assert!(!tdo.lists[0].list[0].done);
+        assert!(tdo.outbox.is_empty());
+    }
+
+    #[test]
+    fn permanent_failures_are_not_queued() {
+        let server = MockServer::start(|_| Reply::json(401, r#"{"message": "Bad credentials"}"#));
+        let mut tdo = container();
+        assert!(remote::close(&mut tdo, &mut online(&server), 1).is_err());
+        assert!(!tdo.lists[0].list[0].done);
+        assert!(tdo.outbox.is_empty());
+    }
+
+    #[test]
+    fn persisted() {
+        let mut tdo = container();
+        remote::comment(&mut tdo, &mut offline(), 1, "Offline").unwrap();
+        let json = serde_json::to_string(&tdo).unwrap();
+        assert!(json.contains(r#""operation":{"type":"comment","body":"Offline"}"#));
+        let loaded: tdo::Tdo = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.outbox, tdo.outbox);
+
+        let empty = serde_json::to_string(&container()).unwrap();
+        assert!(!empty.contains("outbox"));
+    }
+}
@@ -0,0 +1,107 @@
+extern crate tdo_core;
+
+
+#[cfg(test)]
+mod query {
+    use tdo_core::{tdo, list, todo};
+    use tdo_core::error::*;
+    use tdo_core::query::Query;
+
+    fn sample() -> tdo::Tdo {
+        let mut tdo = tdo::Tdo::new();
+        tdo.add_list(list::TodoList::new("Work")).unwrap();
+        tdo.add_todo(Some("work"), todo::Todo::new(0, "Deploy the website", None)).unwrap();
+        tdo.add_todo(Some("work"),
+                      todo::Todo::new(1, "Fix crash on deploy", Some(todo::GitHub::new("tdolist/tdo", 12))))
+            .unwrap();
+        let mut pull = todo::Todo::new(2, "Review the done column", None);
+        pull.pull_request = Some(todo::PullRequest::new("tdolist/tdo", 13));
+        pull.commit = Some(todo::Commit::new("tdolist/tdo", "1A2B3C4D"));
+        tdo.add_todo(None, pull).unwrap();
+        tdo.add_todo(None,
+                      todo::Todo::new(3,
+                                      "Migrate CI",
+                                      Some(todo::RemoteIssue::with_forge(todo::Forge::GitLab, "group/ci", 4))))
+            .unwrap();
+        tdo.done_id(0).unwrap();
+        tdo
+    }
+
+    fn ids(tdo: &tdo::Tdo, query: &str) -> Vec<u32> {
+        tdo.search(query).unwrap().iter().map(|&(_, todo)| todo.id).collect()
+    }
+
+    // Code and position of the error the query causes.
+    fn error(query: &str) -> (&'static str, usize) {
+        match Query::parse(query) {
+            Err(Error(ErrorKind::QueryError(kind), _)) => (kind.code(), kind.position().unwrap()),
+            other => panic!("expected a QueryError for {:?}, got {:?}", query, other),
+        }
+    }
+
+    #[test]
+    fn names() {
+        let tdo = sample();
+        assert_eq!(ids(&tdo, "deploy"), vec![0, 1]);
+        assert_eq!(ids(&tdo, "DEPLOY website"), vec![0]);
+        assert_eq!(ids(&tdo, "\"on deploy\""), vec![1]);
+        assert_eq!(ids(&tdo, "name:\"the done\""), vec![2]);
+        assert_eq!(ids(&tdo, "!deploy"), vec![2, 3]);
+        assert_eq!(ids(&tdo, ""), vec![2, 3, 0, 1]);
+    }
+
+    #[test]
+    fn done_and_lists() {
+        let tdo = sample();
+        assert_eq!(ids(&tdo, "done"), vec![0]);
+        assert_eq!(ids(&tdo, "!done"), vec![2, 3, 1]);
+        assert_eq!(ids(&tdo, "done:no list:work"), vec![1]);
+        assert_eq!(ids(&tdo, "list:WORK !done \"deploy\""), vec![1]);
+        assert_eq!(ids(&tdo, "!list:default"), vec![0, 1]);
+
+        let found = tdo.search("crash").unwrap();
+        assert_eq!(found[0].0.name, "Work");
+    }
+
+    #[test]
+    fn links() {
+        let tdo = sample();
+        assert_eq!(ids(&tdo, "repo:tdolist/tdo"), vec![1]);
+        assert_eq!(ids(&tdo, "github.repo:TdoList/Tdo"), vec![1]);
+        assert_eq!(ids(&tdo, "issue>4"), vec![1]);
+        assert_eq!(ids(&tdo, "github.issue<=12"), vec![3, 1]);
+        assert_eq!(ids(&tdo, "forge:gitlab"), vec![3]);
+        assert_eq!(ids(&tdo, "pr.state:open pr.number:13 pr.repo:tdolist/tdo"), vec![2]);
+        assert_eq!(ids(&tdo, "commit.sha:1a2b commit.repo:tdolist/tdo"), vec![2]);
+        assert_eq!(ids(&tdo, "commit.sha:2b3c"), Vec::<u32>::new());
+        assert_eq!(ids(&tdo, "id>=1 id<3"), vec![2, 1]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let err = Query::parse("list:work tag:urgent due<2026-11-01 !done \"deploy\"").unwrap_err();
+        assert_eq!(err.to_string(), "Unknown field 'tag' at position 10");
+        assert_eq!(err.report().code, "query.unknown_field");
+        assert_eq!(error("list:work due<2026-11-01"), ("query.unknown_field", 10));
+        assert_eq!(error("name<deploy"), ("query.invalid_operator", 4));
+        assert_eq!(error("done:maybe"), ("query.invalid_value", 5));
+        assert_eq!(error("id>=x"), ("query.invalid_value", 4));
+        assert_eq!(error("forge:bitbucket"), ("query.invalid_value", 6));
+        assert_eq!(error("done list:"), ("query.missing_value", 10));
+        assert_eq!(error("deploy !"), ("query.missing_value", 8));
+        assert_eq!(error("done \"deploy"), ("query.unterminated_quote", 5));
+    }
+
+    #[test]
+    fn positions_count_characters() {
+        let err = Query::parse("\"Überprüfen\" größe:1").unwrap_err();
+        assert_eq!(err.to_string(), "Unknown field 'größe' at position 13");
+    }
+
+    #[test]
+    fn display() {
+        let query: Query = "list:work !done".parse().unwrap();
+        assert_eq!(query.to_string(), "list:work !done");
+        assert_eq!(query.as_str(), "list:work !done");
+    }
+}
@@ -0,0 +1,92 @@
+extern crate tdo_core;
+extern crate serde_json;
+
+
+#[cfg(test)]
+mod searches {
+    use tdo_core::{tdo, list, query, todo};
+    use tdo_core::error::*;
+
+    fn sample() -> tdo::Tdo {
+        let mut tdo = tdo::Tdo::new();
+        tdo.add_list(list::TodoList::new("work")).unwrap();
+        tdo.add_todo(Some("work"), todo::Todo::new(0, "Deploy the website", None)).unwrap();
+        tdo.add_todo(None, todo::Todo::new(1, "Deploy the dotfiles", None)).unwrap();
+        tdo.add_todo(None, todo::Todo::new(2, "Water the plants", None)).unwrap();
+        tdo.add_search(query::SavedSearch::new("Deployments", "deploy !done").unwrap()).unwrap();
+        tdo
+    }
+
+    fn ids(view: &list::ListView) -> Vec<u32> {
+        view.todos.iter().map(|todo| todo.id).collect()
+    }
+
+    #[test]
+    fn views() {
+        let mut tdo = sample();
+        let views = tdo.views();
+        let names: Vec<&str> = views.iter().map(|view| view.name).collect();
+        assert_eq!(names, vec!["default", "work", "Deployments"]);
+        assert!(!views[0].is_virtual());
+        assert_eq!(ids(&views[0]), vec![1, 2]);
+        assert!(views[2].is_virtual());
+        assert_eq!(ids(&views[2]), vec![1, 0]);
+        assert_eq!(views[2].query.unwrap().as_str(), "deploy !done");
+
+        tdo.done_id(1).unwrap();
+        assert_eq!(ids(&tdo.view("deployments").unwrap()), vec![0]);
+        assert_eq!(ids(&tdo.view("work").unwrap()), vec![0]);
+        assert!(tdo.view("nothing").is_err());
+    }
+
+    #[test]
+    fn not_a_target() {
+        let mut tdo = sample();
+        for result in [tdo.add_todo(Some("Deployments"), todo::Todo::new(3, "New", None)),
+                       tdo.move_todo(2, "deployments"),
+                       tdo.clean_list("Deployments")] {
+            match result {
+                Err(Error(ErrorKind::TodoError(todo_error::ErrorKind::VirtualList(name)), _)) => {
+                    assert_eq!(name.to_lowercase(), "deployments")
+                }
+                other => panic!("expected VirtualList, got {:?}", other),
+            }
+        }
+        assert_eq!(tdo.lists[0].list.len(), 2);
+        assert_eq!(tdo.get_highest_id(), 2);
+    }
+
+    #[test]
+    fn unique_names() {
+        let mut tdo = sample();
+        assert!(tdo.add_list(list::TodoList::new("deployments")).is_err());
+        assert!(tdo.add_search(query::SavedSearch::new("Work", "done").unwrap()).is_err());
+        assert!(tdo.add_search(query::SavedSearch::new("DEPLOYMENTS", "done").unwrap()).is_err());
+        assert_eq!(tdo.lists.len(), 2);
+        assert_eq!(tdo.searches.len(), 1);
+
+        let removed = tdo.remove_search("deployments").unwrap();
+        assert_eq!(removed.name, "Deployments");
+        assert!(tdo.remove_search("deployments").is_err());
+        tdo.add_list(list::TodoList::new("deployments")).unwrap();
+    }
+
+    #[test]
+    fn invalid_query() {
+        assert!(query::SavedSearch::new("Later", "due<2026-11-01").is_err());
+    }
+
+    #[test]
+    fn serde() {
+        let json = serde_json::to_value(sample()).unwrap();
+        assert_eq!(json["searches"][0]["query"], "deploy !done");
+        assert!(serde_json::to_value(tdo::Tdo::new()).unwrap().get("searches").is_none());
+
+        let loaded: tdo::Tdo = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(loaded.searches, sample().searches);
+
+        let mut broken = json;
+        broken["searches"][0]["query"] = serde_json::Value::from("tag:urgent");
+        assert!(serde_json::from_value::<tdo::Tdo>(broken).is_err());
+    }
+}
@@ -0,0 +1,77 @@
+extern crate tdo_core;
+extern crate log;
+
+
+#[cfg(test)]
+mod silent {
+    use std::env;
+    use std::process::Command;
+    use std::sync::Mutex;
+    use log::{self, Log, Metadata, Record, LevelFilter};
+    use tdo_core::{storage, tdo};
+
+    struct Collector {
+        messages: Mutex<Vec<String>>,
+    }
+
+    impl Log for Collector {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            self.messages.lock().unwrap().push(format!("{}", record.args()));
+        }
+
+        fn flush(&self) {}
+    }
+
+    static COLLECTOR: Collector = Collector { messages: Mutex::new(Vec::new()) };
+
+    fn load_fixtures() {
+        tdo::Tdo::load("tests/versions/version00.json").unwrap();
+        tdo::Tdo::load("tests/versions/version01.json").unwrap();
+        tdo::Tdo::load("tests/versions/version02.json").unwrap();
+        storage::dry_run("tests/versions/version00_broken.json").unwrap();
+        assert!(tdo::Tdo::load("tests/versions/version99.json").is_err());
+    }
+
+    /// Helper that is run in a child process by `load_writes_nothing_to_stdout`.
+    #[test]
+    #[ignore]
+    fn load_fixtures_between_markers() {
+        println!("<<<");
+        load_fixtures();
+        println!(">>>");
+    }
+
+    #[test]
+    fn load_writes_nothing_to_stdout() {
+        let output = Command::new(env::current_exe().unwrap())
+            .args(["silent::load_fixtures_between_markers",
+                    "--exact",
+                    "--ignored",
+                    "--nocapture",
+                    "--test-threads=1"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let start = stdout.find("<<<\n").expect("helper test did not run") + 4;
+        let end = stdout.find(">>>").unwrap();
+        assert_eq!(&stdout[start..end], "");
+    }
+
+    #[test]
+    fn diagnostics_go_to_logger() {
+        log::set_logger(&COLLECTOR).unwrap();
+        log::set_max_level(LevelFilter::Trace);
+
+        load_fixtures();
+        let messages = COLLECTOR.messages.lock().unwrap();
+        assert!(messages.iter().any(|m| m.contains("from schema 0.0 to 0.1")));
+        assert!(messages.iter().any(|m| m.contains("\"two\"")));
+        assert!(messages.iter().any(|m| m.contains("newer")));
+    }
+}
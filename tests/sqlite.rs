@@ -0,0 +1,270 @@
+#![cfg(feature = "sqlite")]
+extern crate tdo_core;
+extern crate rusqlite;
+
+
+#[cfg(test)]
+mod sqlite {
+    use std::env;
+    use std::fs;
+    use tdo_core::{tdo, list, outbox, query, todo};
+    use tdo_core::sqlite::SqliteStorage;
+    use tdo_core::error::*;
+    use tdo_core::storage::{self, Storage, JsonFile, MemoryStorage};
+
+    fn sample() -> tdo::Tdo {
+        let mut tdo = tdo::Tdo::new();
+        tdo.add_list(list::TodoList::new("test")).unwrap();
+        tdo.add_todo(Some("test"), todo::Todo::new(0, "First Entry", None)).unwrap();
+        tdo.add_todo(Some("test"),
+                      todo::Todo::new(1, "Issue", Some(todo::GitHub::new("tdolist/tdo", 42))))
+            .unwrap();
+        tdo.add_todo(None, todo::Todo::new(2, "Third Entry", None)).unwrap();
+        tdo.done_id(2).unwrap();
+        tdo.set_gh_token("token");
+        tdo
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut storage = SqliteStorage::open_in_memory().unwrap();
+        storage.save(&sample()).unwrap();
+
+        let loaded = storage.load().unwrap();
+        assert_eq!(loaded.lists.len(), 2);
+        assert_eq!(loaded.lists[1].name, "test");
+        assert_eq!(loaded.lists[1].list[1].github.as_ref().unwrap().issue_number, 42);
+        assert!(loaded.lists[0].list[0].done);
+        // the token is kept in a credentials store instead
+        assert_eq!(loaded.get_gh_token(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn load_empty() {
+        SqliteStorage::open_in_memory().unwrap().load().unwrap();
+    }
+
+    #[test]
+    fn incremental_update() {
+        let mut storage = SqliteStorage::open_in_memory().unwrap();
+        let mut tdo = sample();
+        storage.save(&tdo).unwrap();
+
+        tdo.remove_id(0).unwrap();
+        tdo.lists[1].list[0].github = None;
+        tdo.lists[1].list[0].edit("Renamed");
+        tdo.move_todo(1, "default").unwrap();
+        tdo.remove_list("test").unwrap();
+        storage.save(&tdo).unwrap();
+
+        let loaded = storage.load().unwrap();
+        assert_eq!(loaded.lists.len(), 1);
+        assert_eq!(loaded.lists[0].list.len(), 2);
+        assert_eq!(loaded.lists[0].list[0].name, "Renamed");
+        assert!(loaded.lists[0].list[0].github.is_none());
+        assert!(loaded.find_id(0).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn duplicate_ids() {
+        let mut tdo = sample();
+        tdo.add_todo(None, todo::Todo::new(0, "Duplicate", None)).unwrap();
+        SqliteStorage::open_in_memory().unwrap().save(&tdo).unwrap();
+    }
+
+    #[test]
+    fn convert_json() {
+        let json_path = env::temp_dir().join("tdo_sqlite_convert.json");
+        let db_path = env::temp_dir().join("tdo_sqlite_convert.sqlite");
+        let _ = fs::remove_file(&db_path);
+
+        let mut json = JsonFile::new(json_path.to_str().unwrap());
+        json.save(&sample()).unwrap();
+
+        let mut db = SqliteStorage::open(db_path.to_str().unwrap()).unwrap();
+        storage::convert(&json, &mut db).unwrap();
+
+        let mut back = MemoryStorage::new();
+        storage::convert(&SqliteStorage::open(db_path.to_str().unwrap()).unwrap(), &mut back)
+            .unwrap();
+        let loaded = back.load().unwrap();
+        assert_eq!(loaded.lists[1].list[0].name, "First Entry");
+        assert_eq!(loaded.get_highest_id(), 2);
+    }
+
+    #[test]
+    fn forge_roundtrip() {
+        let mut storage = SqliteStorage::open_in_memory().unwrap();
+        let mut tdo = sample();
+        tdo.lists[1].list[1].github = Some(todo::RemoteIssue::with_forge(todo::Forge::GitLab, "group/project", 7));
+        storage.save(&tdo).unwrap();
+
+        let loaded = storage.load().unwrap();
+        let link = loaded.lists[1].list[1].github.clone().unwrap();
+        assert_eq!(link, todo::RemoteIssue::with_forge(todo::Forge::GitLab, "group/project", 7));
+    }
+
+    #[test]
+    fn comments_and_activity() {
+        let mut storage = SqliteStorage::open_in_memory().unwrap();
+        let mut tdo = sample();
+        storage.save(&tdo).unwrap();
+
+        tdo.lists[1].list[0].comment("Needs a second look");
+        tdo.lists[1].list[0].edit("First entry");
+        storage.save(&tdo).unwrap();
+        let loaded = storage.load().unwrap();
+        assert_eq!(loaded.lists[1].list[0].comments, tdo.lists[1].list[0].comments);
+        assert_eq!(loaded.lists[1].list[0].activity, tdo.lists[1].list[0].activity);
+        assert!(loaded.lists[1].list[1].comments.is_empty());
+    }
+
+    #[test]
+    fn saved_searches() {
+        let mut storage = SqliteStorage::open_in_memory().unwrap();
+        let mut tdo = sample();
+        tdo.add_search(query::SavedSearch::new("Today", "!done list:test").unwrap()).unwrap();
+        storage.save(&tdo).unwrap();
+        assert_eq!(storage.load().unwrap().searches, tdo.searches);
+
+        tdo.remove_search("today").unwrap();
+        storage.save(&tdo).unwrap();
+        assert!(storage.load().unwrap().searches.is_empty());
+    }
+
+    #[test]
+    fn cache_validators() {
+        let mut storage = SqliteStorage::open_in_memory().unwrap();
+        let mut tdo = sample();
+        storage.save(&tdo).unwrap();
+
+        let etag = Some("\"abc\"".to_string());
+        tdo.lists[1].list[1].github.as_mut().unwrap().cache.etag = etag.clone();
+        storage.save(&tdo).unwrap();
+        let loaded = storage.load().unwrap();
+        let cache = &loaded.lists[1].list[1].github.as_ref().unwrap().cache;
+        assert_eq!(cache.etag, etag);
+        assert_eq!(cache.last_modified, None);
+    }
+
+    #[test]
+    fn pull_request_and_commit_roundtrip() {
+        let mut storage = SqliteStorage::open_in_memory().unwrap();
+        let mut tdo = sample();
+        let mut pull = todo::PullRequest::new("tdolist/tdo", 12);
+        pull.state = todo::PullState::Draft;
+        tdo.lists[0].list[0].pull_request = Some(pull.clone());
+        tdo.lists[0].list[0].commit = Some(todo::Commit::with_forge(todo::Forge::Gitea, "me/tdo", "1f2e3d4"));
+        storage.save(&tdo).unwrap();
+        assert_eq!(storage.load().unwrap().lists[0].list[0].pull_request, Some(pull));
+
+        tdo.lists[0].list[0].pull_request.as_mut().unwrap().state = todo::PullState::Merged;
+        tdo.lists[0].list[0].commit = None;
+        storage.save(&tdo).unwrap();
+        let loaded = storage.load().unwrap();
+        assert_eq!(loaded.lists[0].list[0].pull_request.clone().unwrap().state, todo::PullState::Merged);
+        assert_eq!(loaded.lists[0].list[0].commit, None);
+    }
+
+    #[test]
+    fn outbox_roundtrip() {
+        let mut storage = SqliteStorage::open_in_memory().unwrap();
+        let mut tdo = sample();
+        tdo.outbox.push(outbox::PendingOperation {
+            todo_id: 2,
+            issue: todo::GitHub::new("tdolist/tdo", 42),
+            operation: outbox::Operation::Retitle {
+                from: "Issue".to_string(),
+                to: "Renamed".to_string(),
+            },
+            attempts: 1,
+        });
+        storage.save(&tdo).unwrap();
+        assert_eq!(storage.load().unwrap().outbox, tdo.outbox);
+
+        tdo.outbox.clear();
+        storage.save(&tdo).unwrap();
+        assert!(storage.load().unwrap().outbox.is_empty());
+    }
+
+    #[test]
+    fn move_policy_and_list_repo() {
+        let mut storage = SqliteStorage::open_in_memory().unwrap();
+        let mut tdo = sample();
+        storage.save(&tdo).unwrap();
+        assert_eq!(storage.load().unwrap().move_policy, tdo::MovePolicy::Locked);
+
+        tdo.move_policy = tdo::MovePolicy::RepoBound;
+        tdo.lists[1].repo = Some("tdolist/tdo".to_string());
+        storage.save(&tdo).unwrap();
+        let loaded = storage.load().unwrap();
+        assert_eq!(loaded.move_policy, tdo::MovePolicy::RepoBound);
+        assert_eq!(loaded.lists[1].repo, Some("tdolist/tdo".to_string()));
+        assert_eq!(loaded.lists[0].repo, None);
+    }
+
+    #[test]
+    fn list_forge_and_labels() {
+        let mut storage = SqliteStorage::open_in_memory().unwrap();
+        let mut tdo = sample();
+        tdo.add_list(list::TodoList::for_repo("bugs", "tdolist/tdo")
+                .with_forge(todo::Forge::GitLab)
+                .label("bug")
+                .label("good first issue"))
+            .unwrap();
+        storage.save(&tdo).unwrap();
+
+        let loaded = storage.load().unwrap();
+        assert_eq!(loaded.lists[2].forge, todo::Forge::GitLab);
+        assert_eq!(loaded.lists[2].labels, vec!["bug", "good first issue"]);
+        assert!(loaded.lists[1].labels.is_empty());
+        assert_eq!(loaded.lists[1].forge, todo::Forge::GitHub);
+    }
+
+    #[test]
+    fn upgrade_old_database() {
+        let db_path = env::temp_dir().join("tdo_sqlite_upgrade.sqlite");
+        let _ = fs::remove_file(&db_path);
+        {
+            let conn = rusqlite::Connection::open(&db_path).unwrap();
+            conn.execute_batch("
+                CREATE TABLE meta (key TEXT PRIMARY KEY, value TEXT);
+                CREATE TABLE lists (name TEXT PRIMARY KEY, position INTEGER NOT NULL);
+                CREATE TABLE todos (id INTEGER PRIMARY KEY, list TEXT NOT NULL, position INTEGER NOT NULL,
+                                    name TEXT NOT NULL, done INTEGER NOT NULL);
+                CREATE TABLE github (todo_id INTEGER PRIMARY KEY, repo TEXT NOT NULL,
+                                     issue_number INTEGER NOT NULL);
+                INSERT INTO meta VALUES ('version', '0.2.2');
+                INSERT INTO meta VALUES ('access_token', 'OldToken');
+                INSERT INTO lists VALUES ('default', 0);
+                INSERT INTO todos VALUES (0, 'default', 0, 'Issue', 0);
+                INSERT INTO github VALUES (0, 'tdolist/tdo', 3);")
+                .unwrap();
+        }
+
+        let loaded = SqliteStorage::open(db_path.to_str().unwrap()).unwrap().load().unwrap();
+        assert_eq!(loaded.lists[0].list[0].github, Some(todo::GitHub::new("tdolist/tdo", 3)));
+        assert_eq!(loaded.get_gh_token(), Some("OldToken".to_string()));
+    }
+
+    #[test]
+    fn newer_database() {
+        let db_path = env::temp_dir().join("tdo_sqlite_newer.sqlite");
+        let _ = fs::remove_file(&db_path);
+        let mut storage = SqliteStorage::open(db_path.to_str().unwrap()).unwrap();
+        storage.save(&tdo::Tdo::new()).unwrap();
+        {
+            let conn = rusqlite::Connection::open(&db_path).unwrap();
+            conn.execute("UPDATE meta SET value = '9.0.0' WHERE key = 'version'", []).unwrap();
+        }
+
+        match storage.load() {
+            Err(Error(ErrorKind::StorageError(storage_error::ErrorKind::UnsupportedVersion(version)), _)) => {
+                assert_eq!(version, "9.0.0")
+            }
+            other => panic!("expected UnsupportedVersion, got {:?}", other),
+        }
+    }
+}
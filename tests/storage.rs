@@ -0,0 +1,58 @@
+extern crate tdo_core;
+
+
+#[cfg(test)]
+mod backends {
+    use std::env;
+    use tdo_core::{tdo, list, todo};
+    use tdo_core::storage::{Storage, JsonFile, MemoryStorage};
+
+    fn sample() -> tdo::Tdo {
+        let mut tdo = tdo::Tdo::new();
+        tdo.add_list(list::TodoList::new("test")).unwrap();
+        tdo.add_todo(Some("test"), todo::Todo::new(0, "First Entry", None)).unwrap();
+        tdo.add_todo(None, todo::Todo::new(1, "Second Entry", None)).unwrap();
+        tdo
+    }
+
+    #[test]
+    fn memory_roundtrip() {
+        let mut storage = MemoryStorage::new();
+        storage.save(&sample()).unwrap();
+
+        let loaded = storage.load().unwrap();
+        assert_eq!(loaded.lists.len(), 2);
+        assert_eq!(loaded.lists[1].list[0].name, "First Entry");
+        assert_eq!(loaded.lists[0].list[0].name, "Second Entry");
+    }
+
+    #[test]
+    #[should_panic]
+    fn memory_empty() {
+        MemoryStorage::new().load().unwrap();
+    }
+
+    #[test]
+    fn json_file_roundtrip() {
+        let path = env::temp_dir().join("tdo_storage_roundtrip.json");
+        let mut storage = JsonFile::new(path.to_str().unwrap());
+        storage.save(&sample()).unwrap();
+
+        let loaded = tdo::Tdo::load(storage.path()).unwrap();
+        assert_eq!(loaded.lists[1].list[0].name, "First Entry");
+    }
+
+    #[test]
+    fn generic_backend() {
+        fn bump<S: Storage>(storage: &mut S) {
+            let mut tdo = storage.load().unwrap();
+            let id = tdo.get_highest_id() + 1;
+            tdo.add_todo(None, todo::Todo::new(id, "Added through the trait", None)).unwrap();
+            storage.save(&tdo).unwrap();
+        }
+
+        let mut storage = MemoryStorage::with_tdo(sample());
+        bump(&mut storage);
+        assert_eq!(storage.load().unwrap().get_highest_id(), 2);
+    }
+}
@@ -0,0 +1,175 @@
+#![cfg(feature = "webhook")]
+extern crate tdo_core;
+
+
+#[cfg(test)]
+mod webhook {
+    use std::fs;
+    use tdo_core::{tdo, list, todo};
+    use tdo_core::error::*;
+    use tdo_core::webhook::{self, Action, Change, Event, Subject};
+
+    const SECRET: &[u8] = b"tdo-webhook-secret";
+
+    fn fixture(name: &str) -> Vec<u8> {
+        fs::read(format!("tests/webhooks/{}.json", name)).unwrap()
+    }
+
+    fn signature(name: &str) -> String {
+        let signatures = fs::read_to_string("tests/webhooks/signatures.txt").unwrap();
+        signatures.lines()
+            .filter_map(|line| line.split_once(' '))
+            .find(|&(fixture, _)| fixture == name)
+            .map(|(_, signature)| signature.to_string())
+            .unwrap()
+    }
+
+    fn deliver(tdo: &mut tdo::Tdo, event: &str, name: &str) -> TdoResult<Change> {
+        webhook::handle(tdo, SECRET, event, &signature(name), &fixture(name), Some("github"))
+    }
+
+    fn container() -> tdo::Tdo {
+        let mut tdo = tdo::Tdo::new();
+        tdo.add_list(list::TodoList::new("github")).unwrap();
+        tdo
+    }
+
+    #[test]
+    fn issue_lifecycle() {
+        let mut tdo = container();
+        assert_eq!(deliver(&mut tdo, "issues", "issues_opened").unwrap(), Change::Created(1));
+        assert_eq!(tdo.lists[1].list[0].name, "Crash on empty list");
+        assert_eq!(tdo.lists[1].list[0].github, Some(todo::GitHub::new("tdolist/tdo", 12)));
+
+        assert_eq!(deliver(&mut tdo, "issues", "issues_edited").unwrap(), Change::Retitled(1));
+        assert_eq!(tdo.lists[1].list[0].name, "Crash when a list is empty");
+        assert_eq!(deliver(&mut tdo, "issues", "issues_closed").unwrap(), Change::Closed(1));
+        assert!(tdo.lists[1].list[0].done);
+        assert_eq!(deliver(&mut tdo, "issues", "issues_closed").unwrap(), Change::Unchanged);
+        assert_eq!(deliver(&mut tdo, "issues", "issues_reopened").unwrap(), Change::Reopened(1));
+        assert!(!tdo.lists[1].list[0].done);
+        assert_eq!(tdo.lists[1].list.len(), 1);
+    }
+
+    #[test]
+    fn lists_tracking_the_repository() {
+        let mut tdo = container();
+        let gitlab = list::TodoList::for_repo("gitlab", "tdolist/tdo").with_forge(todo::Forge::GitLab);
+        tdo.add_list(gitlab.label("bug")).unwrap();
+        tdo.add_list(list::TodoList::for_repo("core", "tdolist/tdo-core").label("bug")).unwrap();
+        tdo.add_list(list::TodoList::for_repo("features", "tdolist/tdo").label("feature")).unwrap();
+        tdo.add_list(list::TodoList::for_repo("bugs", "tdolist/tdo").label("bug").label("UI")).unwrap();
+
+        assert_eq!(deliver(&mut tdo, "issues", "issues_opened_bug").unwrap(), Change::Created(1));
+        assert_eq!(tdo.lists[5].list[0].github, Some(todo::GitHub::new("tdolist/tdo", 15)));
+        assert_eq!(deliver(&mut tdo, "issues", "issues_opened").unwrap(), Change::Created(2));
+        assert_eq!(tdo.lists[1].list[0].github, Some(todo::GitHub::new("tdolist/tdo", 12)));
+
+        tdo.add_list(list::TodoList::for_repo("all", "tdolist/tdo")).unwrap();
+        tdo.remove_id(2).unwrap();
+        assert_eq!(deliver(&mut tdo, "issues", "issues_opened").unwrap(), Change::Created(2));
+        assert_eq!(tdo.lists[6].list[0].name, "Crash on empty list");
+    }
+
+    #[test]
+    fn existing_todo() {
+        let mut tdo = container();
+        tdo.add_todo(None,
+                      todo::Todo::new(4, "Crash on empty list", Some(todo::GitHub::new("tdolist/tdo", 12))))
+            .unwrap();
+        assert_eq!(deliver(&mut tdo, "issues", "issues_opened").unwrap(), Change::Unchanged);
+        assert_eq!(deliver(&mut tdo, "issues", "issues_closed").unwrap(), Change::Closed(4));
+        assert!(tdo.lists[1].list.is_empty());
+    }
+
+    #[test]
+    fn pull_request_lifecycle() {
+        let mut tdo = container();
+        assert_eq!(deliver(&mut tdo, "pull_request", "pull_request_opened").unwrap(),
+                   Change::Created(1));
+        let state = |tdo: &tdo::Tdo| tdo.lists[1].list[0].pull_request.as_ref().unwrap().state;
+        assert_eq!(state(&tdo), todo::PullState::Draft);
+
+        assert_eq!(deliver(&mut tdo, "pull_request", "pull_request_ready_for_review").unwrap(),
+                   Change::Updated(1));
+        assert_eq!(state(&tdo), todo::PullState::Open);
+        assert_eq!(deliver(&mut tdo, "pull_request", "pull_request_merged").unwrap(),
+                   Change::Closed(1));
+        assert_eq!(state(&tdo), todo::PullState::Merged);
+        assert!(tdo.lists[1].list[0].done);
+    }
+
+    #[test]
+    fn closed_pull_request_stays_undone() {
+        let mut tdo = container();
+        let mut todo = todo::Todo::new(0, "Abandoned approach", None);
+        todo.pull_request = Some(todo::PullRequest::new("tdolist/tdo", 14));
+        tdo.add_todo(None, todo).unwrap();
+
+        assert_eq!(deliver(&mut tdo, "pull_request", "pull_request_closed").unwrap(),
+                   Change::Updated(0));
+        assert!(!tdo.lists[0].list[0].done);
+    }
+
+    #[test]
+    fn ignored() {
+        let mut tdo = container();
+        assert_eq!(deliver(&mut tdo, "issues", "issues_labeled").unwrap(), Change::Unchanged);
+        assert_eq!(deliver(&mut tdo, "issues", "issues_closed").unwrap(), Change::Unchanged);
+        assert_eq!(deliver(&mut tdo, "push", "issues_opened").unwrap(), Change::Unchanged);
+        assert!(tdo.lists[1].list.is_empty());
+    }
+
+    #[test]
+    fn parse() {
+        let event = Event::parse("pull_request", &fixture("pull_request_merged")).unwrap().unwrap();
+        assert_eq!(event.action, Action::Closed);
+        assert_eq!(event.title, "Handle empty lists");
+        match event.subject {
+            Subject::PullRequest(pull) => {
+                assert_eq!((pull.repo.as_str(), pull.number, pull.state),
+                           ("tdolist/tdo", 13, todo::PullState::Merged))
+            }
+            other => panic!("expected a pull request, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn invalid_signature() {
+        let mut tdo = container();
+        let tampered = String::from_utf8(fixture("issues_opened")).unwrap().replace("Crash", "Hack");
+        for (signature, payload) in [(signature("issues_opened"), tampered.into_bytes()),
+                                     (signature("issues_closed"), fixture("issues_opened")),
+                                     ("sha1=abc".to_string(), fixture("issues_opened")),
+                                     ("sha256=zz".to_string(), fixture("issues_opened"))] {
+            match webhook::handle(&mut tdo, SECRET, "issues", &signature, &payload, None) {
+                Err(Error(ErrorKind::GithubError(github_error::ErrorKind::InvalidSignature), _)) => {}
+                other => panic!("expected InvalidSignature, got {:?}", other),
+            }
+        }
+        assert!(tdo.lists.iter().all(|list| list.list.is_empty()));
+    }
+
+    #[test]
+    fn plus_sign_in_signature() {
+        let payload = br#"{"n": 14}"#;
+        let signature = "sha256=0ebaa7fb2b88b6928128008d73332b4d991def90e549f1d0a37ed701b8ca8e30";
+        assert!(webhook::verify(SECRET, payload, signature).is_ok());
+        // "+e" parses as the same byte as "0e"
+        match webhook::verify(SECRET, payload, &signature.replacen("=0", "=+", 1)) {
+            Err(Error(ErrorKind::GithubError(github_error::ErrorKind::InvalidSignature), _)) => {}
+            other => panic!("expected InvalidSignature, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn invalid_payload() {
+        match Event::parse("issues", br#"{"action": "opened", "repository": {"full_name": "tdolist/tdo"}}"#) {
+            Err(Error(ErrorKind::GithubError(github_error::ErrorKind::InvalidPayload(ref event)), _)) => {
+                assert_eq!(event, "issues")
+            }
+            other => panic!("expected InvalidPayload, got {:?}", other),
+        }
+        assert!(Event::parse("issues", b"not json").is_err());
+    }
+}